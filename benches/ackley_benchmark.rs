@@ -1,14 +1,19 @@
 use ackley_mc::ackley_mc::build_amcparams;
-use ackley_mc::ackley_mc::{ackley, run_ackley_mc};
+use ackley_mc::ackley_mc::AckleyMcParams;
+use ackley_mc::ackley_mc::{ackley, run_ackley_mc, run_mc_with_sink};
+use ackley_mc::landscapes::Rastrigin;
+use ackley_mc::trajectory::SummarySink;
+use ackley_mc::transform::{random_rotation, Rotated, Shifted};
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::f32::consts::PI;
 use std::time::Duration;
 
 // -------------------- pure ackley benchmarks --------------------
 
 fn cbench_ackley_100k_dim(c: &mut Criterion) {
-    let xarr: Vec<f32> = vec![3.1415; 100000];
+    let xarr: Vec<f32> = vec![PI; 100000];
     c.bench_function("ackley 100k dim", |b| {
-        b.iter(|| black_box(ackley(black_box(xarr.clone()))))
+        b.iter(|| black_box(ackley(black_box(&xarr))))
     });
 }
 
@@ -17,7 +22,7 @@ fn cbench_ackley_100k_dim(c: &mut Criterion) {
 #[allow(dead_code)]
 fn cbench_amc_3d_100k_steps(c: &mut Criterion) {
     // parameters from given example file
-    let mut params = build_amcparams();
+    let mut params: AckleyMcParams<f32> = build_amcparams();
     params.n_step = 100000;
     params.ini_temp = 0.1;
     params.final_temp = 0.1;
@@ -35,7 +40,7 @@ fn cbench_amc_3d_500k_steps(c: &mut Criterion) {
     group.measurement_time(Duration::from_secs(10));
 
     // parameters from given example file
-    let mut params = build_amcparams();
+    let mut params: AckleyMcParams<f32> = build_amcparams();
     params.n_step = 500000;
     params.ini_temp = 0.1;
     params.final_temp = 0.1;
@@ -54,7 +59,7 @@ fn cbench_amc_3d_500k_steps_simanneal(c: &mut Criterion) {
     group.measurement_time(Duration::from_secs(10));
 
     // parameters from given example file
-    let mut params = build_amcparams();
+    let mut params: AckleyMcParams<f32> = build_amcparams();
     params.n_step = 500000;
     params.ini_temp = 0.1;
     params.final_temp = 0.01;
@@ -68,6 +73,58 @@ fn cbench_amc_3d_500k_steps_simanneal(c: &mut Criterion) {
     group.finish();
 }
 
+fn cbench_amc_100d_20k_steps(c: &mut Criterion) {
+    let mut group = c.benchmark_group("amc 100d");
+    group.measurement_time(Duration::from_secs(10));
+
+    let mut params: AckleyMcParams<f32> = build_amcparams();
+    params.n_step = 20000;
+    params.ini_temp = 0.1;
+    params.final_temp = 0.1;
+    params.x_ini = (0..100).map(|i| (i as f32) * 0.05 - 2.5).collect();
+    params.x_delta = 0.5;
+    params.seed = 1699;
+
+    group.bench_function("Ackley MC, 100D, 20k steps", |b| {
+        b.iter(|| black_box(run_ackley_mc(black_box(params.clone()))))
+    });
+
+    params.delta_eval = true;
+    group.bench_function("Ackley MC, 100D, 20k steps, delta eval", |b| {
+        b.iter(|| black_box(run_ackley_mc(black_box(params.clone()))))
+    });
+    group.finish();
+}
+
+fn cbench_transformed_mc_20d_100k_steps(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mc transformed");
+    group.measurement_time(Duration::from_secs(10));
+
+    let mut params: AckleyMcParams<f32> = build_amcparams();
+    params.n_step = 100000;
+    params.ini_temp = 1.0;
+    params.final_temp = 1.0;
+    params.x_ini = vec![0.5; 20];
+    params.x_delta = 0.5;
+    params.seed = 1699;
+    let shift: Vec<f32> = (0..20).map(|i| (i as f32) * 0.1 - 1.0).collect();
+    let objective = Shifted::new(Rotated::new(Rastrigin, random_rotation(20, 7)), shift);
+
+    // only summary statistics are kept, so neither the steps nor the
+    // evaluations of the wrapped objective allocate
+    group.bench_function("Shifted rotated Rastrigin MC, 20D, 100k steps", |b| {
+        b.iter(|| {
+            let mut sink: SummarySink<f32> = SummarySink::new();
+            black_box(run_mc_with_sink(
+                black_box(params.clone()),
+                &objective,
+                &mut sink,
+            ))
+        })
+    });
+    group.finish();
+}
+
 // -------------------- running benchmarks --------------------
 
 criterion_group!(
@@ -76,5 +133,7 @@ criterion_group!(
     //cbench_amc_3d_100k_steps,
     cbench_amc_3d_500k_steps,
     cbench_amc_3d_500k_steps_simanneal,
+    cbench_amc_100d_20k_steps,
+    cbench_transformed_mc_20d_100k_steps,
 );
 criterion_main!(benches);
@@ -0,0 +1,259 @@
+use crate::ackley_mc::AckleyMcParams;
+use crate::float::McFloat;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+// -------------------- acceptance rules --------------------
+
+/// Decides whether a trial move from `energy` to `e_trial` is accepted.
+/// `log_correction` is the log of the Hastings correction of asymmetric
+/// (gradient) moves, 0 for symmetric proposals; only the probabilistic rules
+/// use it. Rules may keep state (water level, record), so `accept` is called
+/// exactly once per trial move.
+pub trait AcceptanceRule<F: McFloat> {
+    fn accept(
+        &mut self,
+        energy: F,
+        e_trial: F,
+        temperature: F,
+        log_correction: F,
+        rng: &mut ChaCha8Rng,
+    ) -> bool;
+
+    fn name(&self) -> String;
+}
+
+/// Names accepted by the `acceptance` parameter file key.
+pub const ACCEPTANCE_NAMES: [&str; 6] = [
+    "metropolis",
+    "barker",
+    "tsallis",
+    "threshold",
+    "great_deluge",
+    "record_to_record",
+];
+
+/// The acceptance rule selected by `params.acceptance`.
+pub fn acceptance_from_params<F: McFloat>(
+    params: &AckleyMcParams<F>,
+) -> Option<Box<dyn AcceptanceRule<F>>> {
+    let rule: Box<dyn AcceptanceRule<F>> = match params.acceptance.as_str() {
+        "metropolis" => Box::new(Metropolis),
+        "barker" => Box::new(Barker),
+        "tsallis" => Box::new(Tsallis::new(params.tsallis_q_a)),
+        "threshold" => Box::new(Threshold),
+        "great_deluge" => Box::new(GreatDeluge::new(params.deluge_rain)),
+        "record_to_record" => Box::new(RecordToRecord::new()),
+        _ => return None,
+    };
+    return Some(rule);
+}
+
+/// min(1, exp(-dE / T) * correction). Downhill moves of symmetric proposals
+/// are accepted without drawing a random number.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Metropolis;
+
+impl<F: McFloat> AcceptanceRule<F> for Metropolis {
+    fn accept(
+        &mut self,
+        energy: F,
+        e_trial: F,
+        temperature: F,
+        log_correction: F,
+        rng: &mut ChaCha8Rng,
+    ) -> bool {
+        if e_trial <= energy && log_correction >= F::zero() {
+            return true;
+        }
+        let delta_e: F = energy - e_trial;
+        let log_alpha: F = delta_e / temperature + log_correction;
+        return rng.gen_range(F::zero()..F::one()) < log_alpha.exp();
+    }
+
+    fn name(&self) -> String {
+        return "metropolis".to_string();
+    }
+}
+
+/// Barker's rule, the Glauber (heat-bath) dynamics of a single move:
+/// 1 / (1 + exp(dE / T) / correction). Also rejects some downhill moves.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Barker;
+
+impl<F: McFloat> AcceptanceRule<F> for Barker {
+    fn accept(
+        &mut self,
+        energy: F,
+        e_trial: F,
+        temperature: F,
+        log_correction: F,
+        rng: &mut ChaCha8Rng,
+    ) -> bool {
+        let log_ratio: F = (e_trial - energy) / temperature - log_correction;
+        return rng.gen_range(F::zero()..F::one()) < F::one() / (F::one() + log_ratio.exp());
+    }
+
+    fn name(&self) -> String {
+        return "barker".to_string();
+    }
+}
+
+/// Tsallis & Stariolo's generalised acceptance:
+/// min(1, [1 - (1 - q_a) dE / T]^(1 / (1 - q_a))), 0 if the bracket is not
+/// positive. q_a -> 1 gives Metropolis, q_a < 1 cuts off large uphill moves.
+#[derive(Clone, Copy, Debug)]
+pub struct Tsallis<F: McFloat> {
+    pub q_a: F,
+}
+
+impl<F: McFloat> Tsallis<F> {
+    pub fn new(q_a: F) -> Self {
+        return Tsallis { q_a };
+    }
+}
+
+impl<F: McFloat> AcceptanceRule<F> for Tsallis<F> {
+    fn accept(
+        &mut self,
+        energy: F,
+        e_trial: F,
+        temperature: F,
+        log_correction: F,
+        rng: &mut ChaCha8Rng,
+    ) -> bool {
+        if e_trial <= energy && log_correction >= F::zero() {
+            return true;
+        }
+        let delta_e: F = e_trial - energy;
+        let one_minus_q: F = F::one() - self.q_a;
+        let log_alpha: F = if one_minus_q.abs() < F::epsilon() {
+            -delta_e / temperature
+        } else {
+            let bracket: F = F::one() - one_minus_q * delta_e / temperature;
+            if bracket <= F::zero() {
+                return false;
+            }
+            bracket.ln() / one_minus_q
+        };
+        return rng.gen_range(F::zero()..F::one()) < (log_alpha + log_correction).exp();
+    }
+
+    fn name(&self) -> String {
+        return format!("tsallis (q_a = {})", self.q_a);
+    }
+}
+
+/// Dueck & Scheuer's threshold accepting: deterministic, accepts every move
+/// raising the energy by less than the temperature, used as the threshold.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Threshold;
+
+impl<F: McFloat> AcceptanceRule<F> for Threshold {
+    fn accept(&mut self, energy: F, e_trial: F, temperature: F, _: F, _: &mut ChaCha8Rng) -> bool {
+        return e_trial - energy < temperature;
+    }
+
+    fn name(&self) -> String {
+        return "threshold".to_string();
+    }
+}
+
+/// Dueck's great deluge: accepts every move below the water level, which
+/// starts at the initial energy and sinks by `rain` each step. Ignores the
+/// temperature.
+#[derive(Clone, Copy, Debug)]
+pub struct GreatDeluge<F: McFloat> {
+    pub rain: F,
+    level: Option<F>,
+}
+
+impl<F: McFloat> GreatDeluge<F> {
+    pub fn new(rain: F) -> Self {
+        return GreatDeluge { rain, level: None };
+    }
+}
+
+impl<F: McFloat> AcceptanceRule<F> for GreatDeluge<F> {
+    fn accept(&mut self, energy: F, e_trial: F, _: F, _: F, _: &mut ChaCha8Rng) -> bool {
+        let level: F = self.level.unwrap_or(energy);
+        self.level = Some(level - self.rain);
+        return e_trial <= level;
+    }
+
+    fn name(&self) -> String {
+        return format!("great deluge (rain = {})", self.rain);
+    }
+}
+
+/// Dueck's record-to-record travel: accepts every move below the record
+/// (lowest energy seen) plus a deviation, the temperature.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RecordToRecord<F: McFloat> {
+    record: Option<F>,
+}
+
+impl<F: McFloat> RecordToRecord<F> {
+    pub fn new() -> Self {
+        return RecordToRecord { record: None };
+    }
+}
+
+impl<F: McFloat> AcceptanceRule<F> for RecordToRecord<F> {
+    fn accept(&mut self, energy: F, e_trial: F, temperature: F, _: F, _: &mut ChaCha8Rng) -> bool {
+        let record: F = self.record.unwrap_or(energy).min(e_trial);
+        self.record = Some(record);
+        return e_trial < record + temperature;
+    }
+
+    fn name(&self) -> String {
+        return "record-to-record travel".to_string();
+    }
+}
+
+// -------------------- stochastic tunneling --------------------
+
+/// Wenzel & Hamacher's stochastic tunneling (STUN) transformation
+/// 1 - exp(-gamma (E - e_best)). It maps e_best to 0 and every energy above
+/// it into (0, 1), flattening the landscape above the best energy found so
+/// that the walk tunnels through barriers, while the structure just above
+/// e_best is kept. Larger gamma flattens more.
+#[inline]
+pub fn stun_energy<F: McFloat>(energy: F, e_best: F, gamma: F) -> F {
+    return F::one() - (-gamma * (energy - e_best)).exp();
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_acceptance_rules() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5);
+    let acceptance_rate = |rule: &mut dyn AcceptanceRule<f64>, rng: &mut ChaCha8Rng| {
+        let n_accepted = (0..10000)
+            .filter(|_| rule.accept(1.0, 1.5, 1.0, 0.0, rng))
+            .count();
+        return n_accepted as f64 / 10000.0;
+    };
+    // dE / T = 0.5
+    let metropolis: f64 = (-0.5f64).exp();
+    assert!((acceptance_rate(&mut Metropolis, &mut rng) - metropolis).abs() < 0.02);
+    let barker: f64 = 1.0 / (1.0 + 0.5f64.exp());
+    assert!((acceptance_rate(&mut Barker, &mut rng) - barker).abs() < 0.02);
+    // cut off: 1 - (1 - q_a) dE / T <= 0
+    assert_eq!(acceptance_rate(&mut Tsallis::new(-1.0), &mut rng), 0.0);
+    let tsallis: f64 = (1.0 - 0.5 * 0.5f64).powf(2.0);
+    assert!((acceptance_rate(&mut Tsallis::new(0.5), &mut rng) - tsallis).abs() < 0.02);
+
+    assert!(Threshold.accept(1.0, 1.5, 1.0, 0.0, &mut rng));
+    assert!(!Threshold.accept(1.0, 2.5, 1.0, 0.0, &mut rng));
+
+    let mut deluge: GreatDeluge<f64> = GreatDeluge::new(0.25);
+    assert!(deluge.accept(1.0, 1.0, 1.0, 0.0, &mut rng));
+    assert!(!deluge.accept(1.0, 0.8, 1.0, 0.0, &mut rng));
+    assert!(deluge.accept(1.0, 0.5, 1.0, 0.0, &mut rng));
+
+    let mut rrt: RecordToRecord<f64> = RecordToRecord::new();
+    assert!(rrt.accept(1.0, 0.5, 0.2, 0.0, &mut rng));
+    assert!(!rrt.accept(0.5, 0.8, 0.2, 0.0, &mut rng));
+    assert!(rrt.accept(0.5, 0.6, 0.2, 0.0, &mut rng));
+}
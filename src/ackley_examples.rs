@@ -40,16 +40,16 @@ fn ackley_loop_1d(steps: i32, abs_bound: f32, csv: bool) -> (Vec<f32>, Vec<f32>)
     let path = format!("out/ackley_1d_{steps}steps.csv");
     let mut buffer = File::create(path).unwrap();
 
-    let xrange = linspace::<f32>(-abs_bound as f32, abs_bound as f32, steps as usize);
-    for i in xrange.into_iter() {
-        let x: f32 = i as f32;
-        let y: f32 = ackley(vec![x]);
+    let xrange = linspace::<f32>(-abs_bound, abs_bound, steps as usize);
+    for i in xrange {
+        let x: f32 = i;
+        let y: f32 = ackley(&[x]);
 
         x_vec.push(x);
         y_vec.push(y);
 
         if csv {
-            write!(buffer, "{},{}\n", x, y).ok();
+            writeln!(buffer, "{},{}", x, y).ok();
         }
     }
 
@@ -66,16 +66,16 @@ fn ackley_loop_5d_vary1(steps: i32, abs_bound: f32, csv: bool) -> (Vec<f32>, Vec
     let path = format!("out/ackley_5d_vary1_{steps}steps.csv");
     let mut buffer = File::create(path).unwrap();
 
-    let xrange = linspace::<f32>(-abs_bound as f32, abs_bound as f32, steps as usize);
-    for i in xrange.clone().into_iter() {
-        let y: f32 = ackley(vec![i as f32, const_val, const_val, const_val, const_val]);
-        x1_vec.push(i as f32);
+    let xrange = linspace::<f32>(-abs_bound, abs_bound, steps as usize);
+    for i in xrange.clone() {
+        let y: f32 = ackley(&[i, const_val, const_val, const_val, const_val]);
+        x1_vec.push(i);
         y_vec.push(y);
 
         if csv {
-            write!(
+            writeln!(
                 buffer,
-                "{},{},{},{},{},{}\n",
+                "{},{},{},{},{},{}",
                 i, const_val, const_val, const_val, const_val, y
             )
             .ok();
@@ -94,16 +94,16 @@ fn ackley_loop_2d(steps: i32, abs_bound: f32, csv: bool) -> (Vec<f32>, Vec<f32>,
     let path = format!("out/ackley_2d_{steps}steps.csv");
     let mut buffer = File::create(path).unwrap();
 
-    let xrange = linspace::<f32>(-abs_bound as f32, abs_bound as f32, steps as usize);
-    for i in xrange.clone().into_iter() {
-        for j in xrange.clone().into_iter() {
-            let y: f32 = ackley(vec![i as f32, j as f32]);
-            x1_vec.push(i as f32);
-            x2_vec.push(j as f32);
+    let xrange = linspace::<f32>(-abs_bound, abs_bound, steps as usize);
+    for i in xrange.clone() {
+        for j in xrange.clone() {
+            let y: f32 = ackley(&[i, j]);
+            x1_vec.push(i);
+            x2_vec.push(j);
             y_vec.push(y);
 
             if csv {
-                write!(buffer, "{},{},{}\n", i, j, y).ok();
+                writeln!(buffer, "{},{},{}", i, j, y).ok();
             }
         }
     }
@@ -1,126 +1,554 @@
-use float_cmp::ApproxEq;
+use crate::acceptance::{acceptance_from_params, stun_energy, AcceptanceRule};
+use crate::chain::Chain;
+use crate::float::McFloat;
+use crate::objective::{DeltaObjective, Objective};
+use crate::schedule::{schedule_from_params, CoolingSchedule};
+use crate::sweep::SweepAxis;
+use crate::trajectory::{MemorySink, TrajectorySink};
 use rand::prelude::*;
-use rand_chacha::ChaCha8Rng;
-use std::f32::consts::E;
-use std::f32::consts::PI;
+use std::f64::consts::PI;
 
 // -------------------- ackley --------------------
 
-static ACK_A: f32 = 20.0;
-static ACK_B: f32 = 0.2;
-static ACK_C: f32 = 2.0 * PI;
+static ACK_A: f64 = 20.0;
+static ACK_B: f64 = 0.2;
+static ACK_C: f64 = 2.0 * PI;
 
 #[inline]
-pub fn ackley(x: Vec<f32>) -> f32 {
-    let n = x.len();
+pub fn ackley(x: &[f32]) -> f32 {
+    return Ackley::<f32>::default().evaluate(x);
+}
+
+/// Ackley's function with constants a (funnel depth), b (funnel width) and
+/// c (ripple frequency). The defaults are the classic a = 20, b = 0.2, c = 2 pi.
+#[derive(Clone, Copy, Debug)]
+pub struct Ackley<F: McFloat> {
+    pub a: F,
+    pub b: F,
+    pub c: F,
+}
+
+impl<F: McFloat> Default for Ackley<F> {
+    fn default() -> Self {
+        return Ackley {
+            a: F::lit(ACK_A),
+            b: F::lit(ACK_B),
+            c: F::lit(ACK_C),
+        };
+    }
+}
+
+impl<F: McFloat> Objective<F> for Ackley<F> {
+    #[inline]
+    fn evaluate(&self, x: &[F]) -> F {
+        let n = F::from_usize(x.len()).unwrap();
+
+        let mut square_sum: F = F::zero();
+        let mut cosine_sum: F = F::zero();
+        for &xi in x {
+            square_sum += xi * xi;
+            cosine_sum += (self.c * xi).cos();
+        }
+
+        let mut y: F = F::zero();
+        y += -self.a * (-self.b * (F::one() / n * square_sum).sqrt()).exp();
+        y -= (F::one() / n * cosine_sum).exp();
+        y += self.a + F::E();
+
+        return y;
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return Some((F::lit(-32.768), F::lit(32.768)));
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        return Some((vec![F::zero(); dim], F::zero()));
+    }
+
+    fn name(&self) -> String {
+        return "Ackley".to_string();
+    }
+
+    fn delta(&self) -> Option<&dyn DeltaObjective<F>> {
+        return Some(self);
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        let n = F::from_usize(x.len()).unwrap();
+        let mut square_sum: F = F::zero();
+        let mut cosine_sum: F = F::zero();
+        for &xi in x {
+            square_sum += xi * xi;
+            cosine_sum += (self.c * xi).cos();
+        }
+        let r: F = (square_sum / n).sqrt();
+
+        // The funnel term has a kink at the origin, where 0 is a subgradient.
+        let funnel: F = if r > F::zero() {
+            self.a * self.b * (-self.b * r).exp() / (n * r)
+        } else {
+            F::zero()
+        };
+        let ripple: F = self.c / n * (cosine_sum / n).exp();
+        for (g, &xi) in grad.iter_mut().zip(x) {
+            *g = funnel * xi + ripple * (self.c * xi).sin();
+        }
+        return true;
+    }
+}
+
+/// Running sums: sum(x_i^2) and sum(cos(c x_i)).
+impl<F: McFloat> DeltaObjective<F> for Ackley<F> {
+    fn n_sums(&self) -> usize {
+        return 2;
+    }
 
-    let mut square_sum: f32 = 0.0;
-    let mut cosine_sum: f32 = 0.0;
-    for i in 0..n {
-        square_sum += x[i] * x[i];
-        cosine_sum += (ACK_C * x[i]).cos();
+    #[inline]
+    fn accumulate(&self, sums: &mut [F], _i: usize, xi: F, weight: F) {
+        sums[0] += weight * xi * xi;
+        sums[1] += weight * (self.c * xi).cos();
     }
 
-    let mut y: f32 = 0.0;
-    y += -ACK_A * (-ACK_B * (1.0 / (n as f32) * square_sum).sqrt()).exp();
-    y -= (1.0 / (n as f32) * cosine_sum).exp();
-    y += ACK_A + E;
+    #[inline]
+    fn energy_from_sums(&self, sums: &[F], dim: usize) -> F {
+        let n = F::from_usize(dim).unwrap();
 
-    return y;
+        let mut y: F = F::zero();
+        y += -self.a * (-self.b * (F::one() / n * sums[0].max(F::zero())).sqrt()).exp();
+        y -= (F::one() / n * sums[1]).exp();
+        y += self.a + F::E();
+
+        return y;
+    }
 }
 
 // -------------------- monte carlo --------------------
 
 #[inline]
-pub fn run_ackley_mc(params: AckleyMcParams) -> AckleyMcResult {
-    let mut rng = ChaCha8Rng::seed_from_u64(params.seed);
+pub fn run_ackley_mc<F: McFloat>(params: AckleyMcParams<F>) -> AckleyMcResult<F> {
+    let objective = Ackley {
+        a: params.ack_a,
+        b: params.ack_b,
+        c: params.ack_c,
+    };
+    return run_mc(params, &objective);
+}
+
+/// Runs Metropolis Monte Carlo (with optional simulated annealing) on any
+/// objective, keeping the whole trajectory in the result.
+#[inline]
+pub fn run_mc<F: McFloat, O: Objective<F> + ?Sized>(
+    params: AckleyMcParams<F>,
+    objective: &O,
+) -> AckleyMcResult<F> {
+    let mut sink: MemorySink<F> = MemorySink::with_capacity(params.n_records() as usize);
+    let mut amc_res = run_mc_with_sink(params, objective, &mut sink);
+
+    amc_res.steps = sink.steps;
+    amc_res.accepted_steps = sink.accepted_steps;
+    amc_res.x_vals = sink.x_vals;
+    amc_res.e_vals = sink.e_vals;
+    amc_res.t_vals = sink.t_vals;
+    return amc_res;
+}
+
+/// Like `run_mc`, but hands the recorded states (every `thin`-th step after
+/// `burn_in` steps) to `sink` instead of storing them, so the trajectory
+/// fields of the result stay empty. `e_best`/`x_best` cover the whole run.
+pub fn run_mc_with_sink<F: McFloat, O: Objective<F> + ?Sized>(
+    params: AckleyMcParams<F>,
+    objective: &O,
+    sink: &mut dyn TrajectorySink<F>,
+) -> AckleyMcResult<F> {
+    let mut chain: Chain<F, O> = Chain::new(&params, objective, &params.x_ini, params.seed);
 
     // temperature things for simulated annealing
-    let mut temperature: f32 = params.ini_temp;
-    let anneal: bool = !params.ini_temp.approx_eq(params.final_temp, (0.0, 2));
-    let c_mult: f32 = (1.0 / (params.n_step as f32) * (params.final_temp / temperature).ln()).exp();
+    let mut schedule: Box<dyn CoolingSchedule<F>> = schedule_from_params(&params)
+        .unwrap_or_else(|| panic!("Unknown cooling schedule '{}'", params.schedule));
+    // After a reheat the schedule starts over at `t_offset`, scaled by `t_scale`.
+    let mut t_offset: u64 = 0;
+    let mut t_scale: F = F::one();
+    // steps since e_best last improved (or the last reheat)
+    let mut n_stuck: u64 = 0;
+    let restart_bounds: Vec<(F, F)> = restart_bounds(&params, objective);
 
-    let mut xvec: Vec<f32> = params.x_ini.clone();
-    let mut energy: f32 = ackley(xvec.clone());
+    let mut acceptance: Box<dyn AcceptanceRule<F>> = acceptance_from_params(&params)
+        .unwrap_or_else(|| panic!("Unknown acceptance rule '{}'", params.acceptance));
 
-    let mut e_best: f32 = energy;
-    let mut x_best: Vec<f32> = xvec.clone();
+    // step sizes are adapted during the burn-in only
+    let adapt: bool = params.step_adapt != StepAdapt::None;
 
     // Initialise result struct.
-    let mut amc_res: AckleyMcResult = build_amcresult();
+    let mut amc_res: AckleyMcResult<F> = build_amcresult();
+    amc_res.objective = objective.name();
     amc_res.n_steps = params.n_step;
-    amc_res.x_vals.push(xvec.clone());
-    amc_res.e_vals.push(energy.clone());
+    if adapt {
+        amc_res.x_deltas.push((0, chain.x_deltas.clone()));
+    }
+    // whether the state changed since it was last recorded
+    let mut moved: bool = true;
+    if params.is_recorded(0) {
+        sink.record(0, &chain.x, chain.energy, moved);
+        sink.record_temperature(0, schedule.temperature(0));
+        moved = false;
+    }
 
     for step in 0..params.n_step {
-        let x_trial: Vec<f32> = get_trial_x(xvec.clone(), params.x_delta, &mut rng);
-        let e_trial: f32 = ackley(x_trial.clone());
+        // constant for ini_temp = final_temp, except for schedules ignoring final_temp
+        let temperature: F = t_scale * schedule.temperature(step - t_offset);
 
-        if e_trial < e_best {
-            e_best = e_trial;
-            x_best = x_trial.clone();
+        let e_best: F = chain.e_best;
+        let e_trial: F = chain.propose(temperature);
+        n_stuck += 1;
+        if chain.e_best < e_best {
+            n_stuck = 0;
         }
 
         // evaluate whether to accept the current step
-        let accept: bool;
-        if e_trial <= energy {
-            accept = true;
+        // STUN: the acceptance test sees transformed energies, everything else the raw ones
+        let accept: bool = if params.stun {
+            let e_from: F = stun_energy(chain.energy, chain.e_best, params.stun_gamma);
+            let e_to: F = stun_energy(e_trial, chain.e_best, params.stun_gamma);
+            chain.accept_energies(acceptance.as_mut(), e_from, e_to, temperature)
         } else {
-            let delta_e: f32 = energy - e_trial;
-            accept = rng.gen_range(0.0..1.0) < (delta_e / temperature).exp();
-        }
+            chain.accept_with(acceptance.as_mut(), e_trial, temperature)
+        };
         if accept {
-            xvec = x_trial.clone();
-            energy = e_trial;
+            chain.accept(e_trial);
+            sink.record_accepted(step);
+            moved = true;
+        } else {
+            chain.reject();
+        }
+        schedule.observe(step - t_offset, chain.energy, accept);
 
-            amc_res.accepted_steps.push(step);
+        if adapt && step < params.burn_in {
+            chain.adapt_step_size(accept, params.target_accept);
+            if (step + 1).is_multiple_of(params.thin) || step + 1 == params.burn_in {
+                amc_res.x_deltas.push((step + 1, chain.x_deltas.clone()));
+            }
         }
 
-        amc_res.x_vals.push(xvec.clone());
-        amc_res.e_vals.push(energy);
+        if params.reheat != ReheatPolicy::None && n_stuck >= params.reheat_patience {
+            n_stuck = 0;
+            t_offset = step + 1;
+            t_scale = params.reheat_fraction;
+            schedule.reset();
+            if params.reheat != ReheatPolicy::Reheat {
+                let mut x_restart: Vec<F> = chain.x_best.clone();
+                if params.reheat == ReheatPolicy::RestartRandom {
+                    for (xi, &(lower, upper)) in x_restart.iter_mut().zip(&restart_bounds) {
+                        *xi = chain.rng.gen_range(lower..=upper);
+                    }
+                }
+                chain.set_state(&x_restart);
+                moved = true;
+            }
+            amc_res.reheats.push((step + 1, chain.energy));
+        }
 
-        if anneal {
-            temperature *= c_mult;
+        if params.is_recorded(step + 1) {
+            sink.record(step + 1, &chain.x, chain.energy, moved);
+            sink.record_temperature(step + 1, temperature);
+            moved = false;
         }
     }
 
-    amc_res.e_best = e_best;
-    amc_res.x_best = x_best;
+    sink.finish();
+
+    amc_res.n_accepted = chain.n_accepted;
+    amc_res.e_best = chain.e_best;
+    amc_res.x_best = chain.x_best;
     return amc_res;
 }
 
-#[inline]
-fn get_trial_x(x: Vec<f32>, x_delta: f32, rng: &mut ChaCha8Rng) -> Vec<f32> {
-    let dim: u32 = x.len() as u32;
-    let idim = rng.gen_range(0..dim) as usize;
-    let step: f32 = x_delta * (2.0 * rng.gen_range(0.0..1.0) - 1.0);
+/// Per-dimension ranges random restarts are drawn from: the bounds if set,
+/// else the objective's domain, or the range spanned by x_ini if the domain
+/// is unknown.
+fn restart_bounds<F: McFloat, O: Objective<F> + ?Sized>(
+    params: &AckleyMcParams<F>,
+    objective: &O,
+) -> Vec<(F, F)> {
+    let dim: usize = params.x_ini.len();
+    if !params.lower.is_empty() {
+        return params
+            .lower
+            .iter()
+            .copied()
+            .zip(params.upper.iter().copied())
+            .collect();
+    }
+    if let Some(domain) = objective.domain() {
+        return vec![domain; dim];
+    }
+    let lower: F = params.x_ini.iter().copied().fold(F::infinity(), F::min);
+    let upper: F = params.x_ini.iter().copied().fold(F::neg_infinity(), F::max);
+    return vec![(lower, upper); dim];
+}
 
-    let mut x_trial: Vec<f32> = x.clone();
-    x_trial[idim] += step;
+// -------------------- input and output ackley mc structs --------------------
 
-    return x_trial;
+/// Policies accepted by the `reheat` parameter: never reheat, restart the
+/// schedule in place, or also jump back to x_best or to a random point.
+pub const REHEAT_POLICIES: [&str; 4] = ["none", "reheat", "restart_best", "restart_random"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReheatPolicy {
+    None,
+    Reheat,
+    RestartBest,
+    RestartRandom,
 }
 
-// -------------------- input and output ackley mc structs --------------------
+impl ReheatPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name {
+            "none" => Some(ReheatPolicy::None),
+            "reheat" => Some(ReheatPolicy::Reheat),
+            "restart_best" => Some(ReheatPolicy::RestartBest),
+            "restart_random" => Some(ReheatPolicy::RestartRandom),
+            _ => None,
+        };
+    }
+}
+
+/// Step size adaptation modes: fixed x_delta, one adapted step size for all
+/// dimensions, or one per dimension.
+pub const STEP_ADAPT_MODES: [&str; 3] = ["none", "global", "per_dim"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepAdapt {
+    None,
+    Global,
+    PerDim,
+}
+
+impl StepAdapt {
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name {
+            "none" => Some(StepAdapt::None),
+            "global" => Some(StepAdapt::Global),
+            "per_dim" => Some(StepAdapt::PerDim),
+            _ => None,
+        };
+    }
+}
+
+/// Drivers selectable with the `mode` parameter: a single (annealed) chain,
+/// replica exchange between chains at a ladder of temperatures, a
+/// population of walkers annealed together, a Wang-Landau estimate of the
+/// density of states, Monte Carlo between local minima (basin hopping), or
+/// many independent single chains with different seeds.
+pub const MODES: [&str; 6] = [
+    "mc",
+    "parallel_tempering",
+    "population_annealing",
+    "wang_landau",
+    "basin_hopping",
+    "ensemble",
+];
 
 #[derive(Clone, Debug)]
-pub struct AckleyMcParams {
-    pub ini_temp: f32,
-    pub final_temp: f32,
+pub struct AckleyMcParams<F: McFloat = f32> {
+    /// Driver, see `MODES`.
+    pub mode: String,
+    pub function: String,
+    pub ack_a: F,
+    pub ack_b: F,
+    pub ack_c: F,
+    pub shift: Vec<F>,
+    pub rotation: Vec<Vec<F>>,
+    pub ini_temp: F,
+    pub final_temp: F,
+    /// Name of the cooling schedule, see `schedule::SCHEDULE_NAMES`.
+    pub schedule: String,
+    pub schedule_stairs: u64,
+    /// (step, temperature) pairs; if set, used instead of `schedule`.
+    pub schedule_table: Vec<(u64, F)>,
+    /// Number of steps between updates of the adaptive schedule.
+    pub schedule_window: u64,
+    /// Cooling speed of the adaptive schedule.
+    pub schedule_lambda: F,
+    /// What to do after `reheat_patience` steps without improving e_best,
+    /// see `REHEAT_POLICIES`.
+    pub reheat: ReheatPolicy,
+    pub reheat_patience: u64,
+    /// The schedule starts over at reheat_fraction * ini_temp.
+    pub reheat_fraction: F,
+    /// Number of parallel tempering replicas on the geometric ladder.
+    pub pt_replicas: usize,
+    /// Explicit parallel tempering temperatures, used instead of the ladder.
+    pub pt_temps: Vec<F>,
+    /// Steps between replica swap attempts.
+    pub pt_swap_interval: u64,
+    /// Initial (and target) number of population annealing walkers.
+    pub pa_size: usize,
+    /// Steps per walker between two population annealing temperature changes.
+    pub pa_interval: u64,
+    /// Energy range and number of bins of the Wang-Landau density of states.
+    pub wl_e_min: F,
+    pub wl_e_max: F,
+    pub wl_bins: usize,
+    /// Minimum ratio of every histogram count to the mean for a flat histogram.
+    pub wl_flatness: F,
+    /// Steps between two flatness checks.
+    pub wl_check_interval: u64,
+    /// Modification factor reduction, see `wang_landau::WL_SCHEDULES`.
+    pub wl_schedule: String,
+    /// Stop once ln f falls below this.
+    pub wl_ln_f_final: F,
+    /// Local minimiser of basin hopping, see `basin_hopping::MINIMIZER_NAMES`.
+    pub bh_minimizer: String,
+    /// Maximum number of local minimiser iterations per hop.
+    pub bh_local_steps: u64,
+    /// Convergence tolerance of the local minimiser.
+    pub bh_tolerance: F,
+    /// Initial simplex size or gradient descent step size.
+    pub bh_step: F,
+    /// Local minima closer than this in every coordinate are the same basin.
+    pub bh_basin_radius: F,
+    /// Number of independent chains of the ensemble mode.
+    pub ens_runs: usize,
+    /// Threads the ensemble runs on, 0 for one per available core.
+    pub ens_threads: usize,
+    /// A chain succeeds if it gets within this of the global minimum.
+    pub ens_success: F,
+    /// Parameters given several values; the Cartesian product is run instead.
+    pub sweep: Vec<SweepAxis>,
     pub n_step: u64,
-    pub x_ini: Vec<f32>,
-    pub x_delta: f32,
+    pub x_ini: Vec<F>,
+    pub x_delta: F,
+    /// `metropolis` (uses `proposal`), `mala` or `hmc`, see `gradient::SAMPLER_NAMES`.
+    pub sampler: String,
+    /// Number of leapfrog steps per HMC trial move.
+    pub hmc_steps: u64,
+    /// Acceptance test of the `mc` mode, see `acceptance::ACCEPTANCE_NAMES`.
+    pub acceptance: String,
+    /// Acceptance parameter q_a of the `tsallis` rule.
+    pub tsallis_q_a: F,
+    /// Visiting parameter q_v of the `tsallis` cooling schedule.
+    pub tsallis_q_v: F,
+    /// Energy the `great_deluge` water level sinks by per step.
+    pub deluge_rain: F,
+    /// Apply the stochastic tunneling transformation before the acceptance test.
+    pub stun: bool,
+    /// Flattening strength of the stochastic tunneling transformation.
+    pub stun_gamma: F,
+    /// Per-dimension box the walk is confined to, empty if unbounded.
+    pub lower: Vec<F>,
+    pub upper: Vec<F>,
+    /// Handling of trial points outside the box, see `bounds::BOUNDARY_POLICIES`.
+    pub boundary: String,
+    /// Trial move generator, see `proposal::PROPOSAL_NAMES`.
+    pub proposal: String,
+    /// Step distribution of the `all`, `direction` and `subset` proposals.
+    pub proposal_step: String,
+    /// Number of coordinates the `subset` proposal changes.
+    pub proposal_subset: usize,
+    /// Scale the proposal width with temperature / ini_temp.
+    pub proposal_temp_scaled: bool,
+    /// Steps before the adaptive Metropolis proposal starts using the covariance.
+    pub am_start: u64,
+    /// Regularisation of the adaptive Metropolis covariance.
+    pub am_epsilon: F,
+    /// Step size adaptation during the burn-in, see `STEP_ADAPT_MODES`.
+    pub step_adapt: StepAdapt,
+    /// Acceptance rate the step size adaptation aims for.
+    pub target_accept: F,
+    pub delta_eval: bool,
+    pub delta_refresh: u64,
+    pub trajectory: String,
+    pub burn_in: u64,
+    pub thin: u64,
     pub seed: u64,
     pub foutname: String,
 }
 
-pub fn build_amcparams() -> AckleyMcParams {
+impl<F: McFloat> AckleyMcParams<F> {
+    /// Whether the state after `step` steps (0 = initial state) is recorded,
+    /// i.e. every `thin`-th step once `burn_in` steps are done.
+    #[inline]
+    pub fn is_recorded(&self, step: u64) -> bool {
+        return step >= self.burn_in && (step - self.burn_in).is_multiple_of(self.thin);
+    }
+
+    /// Number of states recorded over the whole run.
+    pub fn n_records(&self) -> u64 {
+        if self.burn_in > self.n_step {
+            return 0;
+        }
+        return (self.n_step - self.burn_in) / self.thin + 1;
+    }
+}
+
+pub fn build_amcparams<F: McFloat>() -> AckleyMcParams<F> {
     let amc_params = AckleyMcParams {
-        ini_temp: 0.1,
-        final_temp: -1.0,
+        mode: "mc".to_string(),
+        function: "ackley".to_string(),
+        ack_a: F::lit(ACK_A),
+        ack_b: F::lit(ACK_B),
+        ack_c: F::lit(ACK_C),
+        shift: vec![],
+        rotation: vec![],
+        ini_temp: F::lit(0.1),
+        final_temp: F::lit(-1.0),
+        schedule: "geometric".to_string(),
+        schedule_stairs: 10,
+        schedule_table: vec![],
+        schedule_window: 100,
+        schedule_lambda: F::lit(0.1),
+        reheat: ReheatPolicy::None,
+        reheat_patience: 1000,
+        reheat_fraction: F::lit(0.5),
+        pt_replicas: 8,
+        pt_temps: vec![],
+        pt_swap_interval: 10,
+        pa_size: 100,
+        pa_interval: 10,
+        wl_e_min: F::zero(),
+        wl_e_max: F::lit(25.0),
+        wl_bins: 100,
+        wl_flatness: F::lit(0.8),
+        wl_check_interval: 10000,
+        wl_schedule: "halve".to_string(),
+        wl_ln_f_final: F::lit(1e-6),
+        bh_minimizer: "nelder_mead".to_string(),
+        bh_local_steps: 1000,
+        bh_tolerance: F::lit(1e-6),
+        bh_step: F::lit(0.1),
+        bh_basin_radius: F::lit(1e-2),
+        ens_runs: 10,
+        ens_threads: 0,
+        ens_success: F::lit(1e-2),
+        sweep: vec![],
         n_step: 100,
-        x_ini: vec![0.0],
-        x_delta: 0.0,
+        x_ini: vec![F::zero()],
+        x_delta: F::zero(),
+        sampler: "metropolis".to_string(),
+        hmc_steps: 10,
+        acceptance: "metropolis".to_string(),
+        tsallis_q_a: F::lit(-5.0),
+        tsallis_q_v: F::lit(2.62),
+        deluge_rain: F::lit(1e-3),
+        stun: false,
+        stun_gamma: F::one(),
+        lower: vec![],
+        upper: vec![],
+        boundary: "reject".to_string(),
+        proposal: "uniform".to_string(),
+        proposal_step: "uniform".to_string(),
+        proposal_subset: 2,
+        proposal_temp_scaled: false,
+        am_start: 1000,
+        am_epsilon: F::lit(1e-6),
+        step_adapt: StepAdapt::None,
+        target_accept: F::lit(0.44),
+        delta_eval: false,
+        delta_refresh: 1000,
+        trajectory: "memory".to_string(),
+        burn_in: 0,
+        thin: 1,
         seed: 3141,
         foutname: "ackley_mc_out.csv".to_string(),
     };
@@ -128,23 +556,41 @@ pub fn build_amcparams() -> AckleyMcParams {
 }
 
 #[derive(Clone, Debug)]
-pub struct AckleyMcResult {
+pub struct AckleyMcResult<F: McFloat = f32> {
+    pub objective: String,
     pub n_steps: u64,
+    pub n_accepted: u64,
+    /// Step index of each recorded state in x_vals/e_vals.
+    pub steps: Vec<u64>,
     pub accepted_steps: Vec<u64>,
-    pub x_vals: Vec<Vec<f32>>,
-    pub e_vals: Vec<f32>,
-    pub x_best: Vec<f32>,
-    pub e_best: f32,
+    pub x_vals: Vec<Vec<F>>,
+    pub e_vals: Vec<F>,
+    /// Temperature each recorded state was sampled at, i.e. the one of the
+    /// trial move leading to it (the initial temperature for step 0).
+    pub t_vals: Vec<F>,
+    /// Step index and energy of the state right after each reheat/restart.
+    pub reheats: Vec<(u64, F)>,
+    /// Step size(s) during the burn-in (every `thin`-th step and at its end),
+    /// one entry or one per dimension. Empty without step size adaptation.
+    pub x_deltas: Vec<(u64, Vec<F>)>,
+    pub x_best: Vec<F>,
+    pub e_best: F,
 }
 
-pub fn build_amcresult() -> AckleyMcResult {
+pub fn build_amcresult<F: McFloat>() -> AckleyMcResult<F> {
     let amc_res = AckleyMcResult {
+        objective: String::new(),
         n_steps: 0,
+        n_accepted: 0,
+        steps: vec![],
         accepted_steps: vec![],
         x_vals: vec![],
         e_vals: vec![],
+        t_vals: vec![],
+        reheats: vec![],
+        x_deltas: vec![],
         x_best: vec![],
-        e_best: 500_000.0,
+        e_best: F::lit(500_000.0),
     };
     return amc_res;
 }
@@ -153,6 +599,320 @@ pub fn build_amcresult() -> AckleyMcResult {
 
 #[test]
 fn test_ackley_atzero_3d() {
-    let res = ackley(vec![0.0, 0.0, 0.0]);
+    let res = ackley(&[0.0, 0.0, 0.0]);
     assert!((res - 0.0).abs() <= 0.0001); // epsilon-like
 }
+
+#[test]
+fn test_run_mc_generic_objective() {
+    struct Sphere;
+    impl Objective<f32> for Sphere {
+        fn evaluate(&self, x: &[f32]) -> f32 {
+            return x.iter().map(|xi| xi * xi).sum();
+        }
+        fn name(&self) -> String {
+            return "Sphere".to_string();
+        }
+    }
+
+    let mut params: AckleyMcParams<f32> = build_amcparams();
+    params.n_step = 2000;
+    params.final_temp = 0.1;
+    params.x_ini = vec![2.0, -2.0];
+    params.x_delta = 0.5;
+
+    let res = run_mc(params, &Sphere);
+    assert_eq!(res.objective, "Sphere");
+    assert_eq!(res.e_vals.len(), 2001);
+    assert!(res.e_best < 8.0);
+    assert!((Sphere.evaluate(&res.x_best) - res.e_best).abs() <= 0.0001);
+}
+
+#[test]
+fn test_ackley_custom_constants() {
+    let shallow: Ackley<f32> = Ackley {
+        a: 5.0,
+        ..Default::default()
+    };
+    assert!(shallow.evaluate(&[0.0, 0.0]).abs() <= 0.0001);
+    assert!(shallow.evaluate(&[1.5, -0.5]) < ackley(&[1.5, -0.5]));
+}
+
+#[test]
+fn test_run_ackley_mc_f64() {
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 1000;
+    params.final_temp = 0.01;
+    params.x_ini = vec![-1.5, 0.5, 1.0];
+    params.x_delta = 0.5;
+
+    let res = run_ackley_mc(params);
+    assert_eq!(res.e_vals.len(), 1001);
+    assert!(res.e_best <= res.e_vals[0]);
+    assert!((Ackley::default().evaluate(&res.x_best) - res.e_best).abs() <= 1e-12);
+}
+
+#[test]
+fn test_run_mc_delta_eval() {
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 5000;
+    params.x_ini = (0..50).map(|i| (i as f64) * 0.1 - 2.5).collect();
+    params.x_delta = 0.5;
+    let full = run_ackley_mc(params.clone());
+
+    params.delta_eval = true;
+    params.delta_refresh = 100;
+    let incremental = run_ackley_mc(params);
+
+    // Same random numbers, and energies equal up to rounding -> same trajectory.
+    assert_eq!(full.accepted_steps, incremental.accepted_steps);
+    assert!((full.e_best - incremental.e_best).abs() <= 1e-9);
+}
+
+#[test]
+fn test_run_mc_with_summary_sink() {
+    use crate::trajectory::SummarySink;
+
+    let mut params: AckleyMcParams<f32> = build_amcparams();
+    params.n_step = 3000;
+    params.x_ini = vec![1.0, -1.0, 0.5];
+    params.x_delta = 0.3;
+    let full = run_ackley_mc(params.clone());
+
+    let mut sink: SummarySink<f32> = SummarySink::new();
+    let streamed = run_mc_with_sink(params, &Ackley::default(), &mut sink);
+    assert!(streamed.x_vals.is_empty());
+    assert_eq!(streamed.e_best, full.e_best);
+    assert_eq!(sink.n_records, 3001);
+    assert_eq!(sink.n_accepted, full.n_accepted + 1);
+    assert_eq!(full.accepted_steps.len() as u64, full.n_accepted);
+}
+
+#[test]
+fn test_run_mc_burn_in_and_thin() {
+    let mut params: AckleyMcParams<f32> = build_amcparams();
+    params.n_step = 1000;
+    params.x_ini = vec![1.0, -1.0];
+    params.x_delta = 0.3;
+    let full = run_ackley_mc(params.clone());
+
+    params.burn_in = 100;
+    params.thin = 7;
+    let thinned = run_ackley_mc(params.clone());
+    assert_eq!(thinned.e_vals.len() as u64, params.n_records());
+    assert_eq!(thinned.steps[0], 100);
+    assert_eq!(thinned.steps[1], 107);
+    assert_eq!(thinned.e_vals[1], full.e_vals[107]);
+    assert_eq!(thinned.e_best, full.e_best);
+    assert_eq!(thinned.n_accepted, full.n_accepted);
+    assert_eq!(thinned.accepted_steps, full.accepted_steps);
+}
+
+#[test]
+fn test_run_mc_cooling_schedules() {
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 2000;
+    params.ini_temp = 1.0;
+    params.final_temp = 0.001;
+    params.x_ini = vec![2.0, -1.5, 1.0];
+    params.x_delta = 0.5;
+
+    for schedule in crate::schedule::SCHEDULE_NAMES {
+        params.schedule = schedule.to_string();
+        let res = run_ackley_mc(params.clone());
+        assert!(res.e_best <= res.e_vals[0], "{schedule}");
+    }
+
+    // A constant table is the same as no annealing at all.
+    let constant = run_ackley_mc(AckleyMcParams {
+        final_temp: 1.0,
+        schedule: "geometric".to_string(),
+        ..params.clone()
+    });
+    params.schedule_table = vec![(0, 1.0)];
+    let table = run_ackley_mc(params);
+    assert_eq!(constant.e_vals, table.e_vals);
+}
+
+#[test]
+fn test_run_mc_adaptive_schedule_records_temperatures() {
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 5000;
+    params.ini_temp = 2.0;
+    params.final_temp = 0.01;
+    params.schedule = "adaptive".to_string();
+    params.x_ini = vec![3.0, -2.5, 1.5];
+    params.x_delta = 0.5;
+    params.thin = 10;
+
+    let res = run_ackley_mc(params.clone());
+    assert_eq!(res.t_vals.len(), res.e_vals.len());
+    assert_eq!(res.t_vals[0], 2.0);
+    assert!(res.t_vals.windows(2).all(|w| w[1] <= w[0]));
+    assert!(*res.t_vals.last().unwrap() < 2.0);
+    assert!(*res.t_vals.last().unwrap() >= 0.01);
+}
+
+#[test]
+fn test_run_mc_reheat_and_restart() {
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 6000;
+    params.ini_temp = 1.0;
+    params.final_temp = 0.001;
+    params.x_ini = vec![4.0, -3.0];
+    params.x_delta = 0.3;
+    params.reheat_patience = 500;
+
+    params.reheat = ReheatPolicy::Reheat;
+    let reheated = run_ackley_mc(params.clone());
+    assert!(!reheated.reheats.is_empty());
+    let (step, _) = reheated.reheats[0];
+    assert!((reheated.t_vals[step as usize + 1] - 0.5).abs() < 1e-12);
+
+    params.reheat = ReheatPolicy::RestartBest;
+    let restarted = run_ackley_mc(params);
+    for &(step, e) in &restarted.reheats {
+        assert_eq!(restarted.e_vals[step as usize], e);
+        assert!(e >= restarted.e_best);
+    }
+}
+
+#[test]
+fn test_run_mc_step_size_adaptation() {
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 20000;
+    params.burn_in = 10000;
+    params.ini_temp = 0.05;
+    params.final_temp = 0.05;
+    params.x_ini = vec![0.1, -0.1];
+    params.x_delta = 5.0;
+    params.step_adapt = StepAdapt::PerDim;
+    params.target_accept = 0.44;
+
+    let res = run_ackley_mc(params.clone());
+    let (last_step, last_deltas) = res.x_deltas.last().unwrap();
+    assert_eq!(*last_step, 10000);
+    assert_eq!(last_deltas.len(), 2);
+    assert!(last_deltas.iter().all(|&d| d < 5.0));
+    // frozen after the burn-in: acceptance over the recorded part near the target
+    let n_recorded: usize = res.accepted_steps.iter().filter(|&&k| k >= 10000).count();
+    let rate = n_recorded as f64 / 10000.0;
+    assert!((rate - 0.44).abs() < 0.1, "acceptance rate {rate}");
+}
+
+#[test]
+fn test_run_mc_proposals() {
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 3000;
+    params.final_temp = 0.01;
+    params.x_ini = vec![2.0, -1.5, 1.0, 0.5];
+    params.x_delta = 0.3;
+    params.delta_eval = true;
+
+    for proposal in crate::proposal::PROPOSAL_NAMES {
+        params.proposal = proposal.to_string();
+        params.proposal_step = "cauchy".to_string();
+        let res = run_ackley_mc(params.clone());
+        // incremental energies of multi-coordinate moves stay consistent
+        let e_last: f64 = *res.e_vals.last().unwrap();
+        let x_last: &Vec<f64> = res.x_vals.last().unwrap();
+        assert!((Ackley::default().evaluate(x_last) - e_last).abs() < 1e-9);
+        assert!(res.e_best <= res.e_vals[0], "{proposal}");
+    }
+}
+
+#[test]
+fn test_run_mc_gradient_samplers() {
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 2000;
+    params.ini_temp = 0.5;
+    params.final_temp = 0.01;
+    params.x_ini = vec![1.2, -0.8, 0.6];
+    params.x_delta = 0.05;
+
+    for sampler in ["mala", "hmc"] {
+        params.sampler = sampler.to_string();
+        let res = run_ackley_mc(params.clone());
+        assert!(res.n_accepted > 100, "{sampler}: {}", res.n_accepted);
+        assert!(res.e_best < res.e_vals[0], "{sampler}");
+        assert!((Ackley::default().evaluate(&res.x_best) - res.e_best).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_run_mc_acceptance_rules() {
+    use crate::acceptance::ACCEPTANCE_NAMES;
+
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 2000;
+    params.ini_temp = 0.5;
+    params.final_temp = 0.01;
+    params.x_ini = vec![1.2, -0.8, 0.6];
+    params.x_delta = 0.3;
+
+    for acceptance in ACCEPTANCE_NAMES {
+        params.acceptance = acceptance.to_string();
+        let res = run_ackley_mc(params.clone());
+        assert!(res.n_accepted > 0, "{acceptance}");
+        assert!(res.e_best < res.e_vals[0], "{acceptance}");
+    }
+}
+
+#[test]
+fn test_run_mc_stun_records_raw_energies() {
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 2000;
+    params.ini_temp = 0.05;
+    params.final_temp = 0.05;
+    params.x_ini = vec![2.0, -3.0];
+    params.x_delta = 0.5;
+    params.stun = true;
+    params.stun_gamma = 2.0;
+
+    let res = run_ackley_mc(params);
+    let ackley: Ackley<f64> = Ackley::default();
+    for (x, &e) in res.x_vals.iter().zip(&res.e_vals) {
+        assert!((ackley.evaluate(x) - e).abs() < 1e-12);
+    }
+    assert!(res.e_best < res.e_vals[0]);
+}
+
+#[test]
+fn test_run_mc_bounds() {
+    use crate::bounds::BOUNDARY_POLICIES;
+
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 2000;
+    params.ini_temp = 5.0;
+    params.final_temp = 5.0;
+    params.x_ini = vec![0.5, -0.5];
+    params.x_delta = 2.0;
+    params.proposal = "all".to_string();
+    params.lower = vec![-1.0, -2.0];
+    params.upper = vec![1.0, 0.0];
+
+    for boundary in BOUNDARY_POLICIES {
+        params.boundary = boundary.to_string();
+        let res = run_ackley_mc(params.clone());
+        assert!(res.n_accepted > 0, "{boundary}");
+        for x in res.x_vals.iter().chain([&res.x_best]) {
+            assert!(
+                x[0] >= -1.0 && x[0] <= 1.0 && x[1] >= -2.0 && x[1] <= 0.0,
+                "{boundary}"
+            );
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "The mala sampler needs boundary reject")]
+fn test_run_mc_gradient_sampler_needs_boundary_reject() {
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 10;
+    params.x_ini = vec![0.5, -0.5];
+    params.sampler = "mala".to_string();
+    params.lower = vec![-1.0, -2.0];
+    params.upper = vec![1.0, 0.0];
+    params.boundary = "reflect".to_string();
+    run_ackley_mc(params);
+}
@@ -0,0 +1,357 @@
+use crate::acceptance::{AcceptanceRule, Metropolis};
+use crate::ackley_mc::AckleyMcParams;
+use crate::bounds::Bounds;
+use crate::chain::Chain;
+use crate::float::McFloat;
+use crate::objective::Objective;
+use crate::schedule::{schedule_from_params, CoolingSchedule};
+
+// -------------------- local minimisers --------------------
+
+/// Local minimisers accepted by the `bh_minimizer` parameter.
+pub const MINIMIZER_NAMES: [&str; 2] = ["nelder_mead", "gradient_descent"];
+
+/// Nelder-Mead downhill simplex starting from `x` and the points x + step e_i.
+/// Stops after `max_iter` iterations or once the energies of the simplex
+/// differ by less than `tol`. Returns the best vertex and its energy.
+pub fn nelder_mead<F: McFloat, O: Objective<F> + ?Sized>(
+    objective: &O,
+    x: &[F],
+    step: F,
+    max_iter: u64,
+    tol: F,
+) -> (Vec<F>, F) {
+    let dim: usize = x.len();
+    let half = F::lit(0.5);
+    let two = F::lit(2.0);
+
+    let mut simplex: Vec<Vec<F>> = vec![x.to_vec()];
+    for i in 0..dim {
+        let mut vertex: Vec<F> = x.to_vec();
+        vertex[i] += step;
+        simplex.push(vertex);
+    }
+    let mut energies: Vec<F> = simplex.iter().map(|v| objective.evaluate(v)).collect();
+    let mut centroid: Vec<F> = vec![F::zero(); dim];
+    let mut trial: Vec<F> = vec![F::zero(); dim];
+
+    // trial = centroid + coeff (worst - centroid)
+    let along = |trial: &mut [F], centroid: &[F], worst: &[F], coeff: F| {
+        for ((t, &c), &w) in trial.iter_mut().zip(centroid).zip(worst) {
+            *t = c + coeff * (w - c);
+        }
+    };
+
+    for _ in 0..max_iter {
+        let mut order: Vec<usize> = (0..=dim).collect();
+        order.sort_by(|&a, &b| energies[a].total_cmp(&energies[b]));
+        let (best, worst, second_worst) = (order[0], order[dim], order[dim - 1]);
+        if energies[worst] - energies[best] < tol {
+            break;
+        }
+
+        centroid.iter_mut().for_each(|c| *c = F::zero());
+        for &k in &order[..dim] {
+            for (c, &v) in centroid.iter_mut().zip(&simplex[k]) {
+                *c += v / F::from_usize(dim).unwrap();
+            }
+        }
+
+        along(&mut trial, &centroid, &simplex[worst], -F::one());
+        let e_reflected: F = objective.evaluate(&trial);
+        if e_reflected < energies[best] {
+            let reflected: Vec<F> = trial.clone();
+            along(&mut trial, &centroid, &simplex[worst], -two);
+            let e_expanded: F = objective.evaluate(&trial);
+            if e_expanded < e_reflected {
+                simplex[worst].copy_from_slice(&trial);
+                energies[worst] = e_expanded;
+            } else {
+                simplex[worst] = reflected;
+                energies[worst] = e_reflected;
+            }
+        } else if e_reflected < energies[second_worst] {
+            simplex[worst].copy_from_slice(&trial);
+            energies[worst] = e_reflected;
+        } else {
+            along(&mut trial, &centroid, &simplex[worst], half);
+            let e_contracted: F = objective.evaluate(&trial);
+            if e_contracted < energies[worst] {
+                simplex[worst].copy_from_slice(&trial);
+                energies[worst] = e_contracted;
+            } else {
+                // shrink towards the best vertex
+                let x_best: Vec<F> = simplex[best].clone();
+                for k in order[1..].iter().copied() {
+                    for (v, &b) in simplex[k].iter_mut().zip(&x_best) {
+                        *v = b + half * (*v - b);
+                    }
+                    energies[k] = objective.evaluate(&simplex[k]);
+                }
+            }
+        }
+    }
+
+    let best: usize = (0..=dim)
+        .min_by(|&a, &b| energies[a].total_cmp(&energies[b]))
+        .unwrap();
+    return (simplex[best].clone(), energies[best]);
+}
+
+/// Steepest descent with backtracking: each iteration starts with step size
+/// `step` and halves it until the energy decreases (Armijo condition). Stops
+/// after `max_iter` iterations or once the gradient norm is below `tol`.
+pub fn gradient_descent<F: McFloat, O: Objective<F> + ?Sized>(
+    objective: &O,
+    x: &[F],
+    step: F,
+    max_iter: u64,
+    tol: F,
+) -> (Vec<F>, F) {
+    let mut x: Vec<F> = x.to_vec();
+    let mut energy: F = objective.evaluate(&x);
+    let mut grad: Vec<F> = vec![F::zero(); x.len()];
+    let mut trial: Vec<F> = vec![F::zero(); x.len()];
+
+    for _ in 0..max_iter {
+        objective.gradient(&x, &mut grad);
+        let grad_norm2: F = grad.iter().map(|&g| g * g).sum();
+        if grad_norm2.sqrt() < tol {
+            break;
+        }
+        let mut eta: F = step;
+        let mut improved: bool = false;
+        // 30 halvings take the step size down by 1e-9
+        for _ in 0..30 {
+            for ((t, &xi), &g) in trial.iter_mut().zip(&x).zip(&grad) {
+                *t = xi - eta * g;
+            }
+            let e_trial: F = objective.evaluate(&trial);
+            if e_trial <= energy - F::lit(1e-4) * eta * grad_norm2 {
+                x.copy_from_slice(&trial);
+                energy = e_trial;
+                improved = true;
+                break;
+            }
+            eta *= F::lit(0.5);
+        }
+        if !improved {
+            break;
+        }
+    }
+    return (x, energy);
+}
+
+// -------------------- basin hopping --------------------
+
+/// A distinct local minimum found during basin hopping.
+#[derive(Clone, Debug)]
+pub struct Basin<F: McFloat = f32> {
+    pub x: Vec<F>,
+    pub energy: F,
+    /// Number of hops that landed in this basin, accepted or not, plus one
+    /// for the initial basin.
+    pub n_visits: u64,
+}
+
+/// One hop from basin `from` (the current one) to the local minimum `to`,
+/// `None` if the hop left the bounds and was rejected.
+#[derive(Clone, Copy, Debug)]
+pub struct Hop<F: McFloat = f32> {
+    pub hop: u64,
+    pub from: usize,
+    pub to: Option<usize>,
+    pub temperature: F,
+    pub accepted: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct BasinHoppingResult<F: McFloat = f32> {
+    pub objective: String,
+    /// Distinct local minima; index 0 is the one x_ini descends into.
+    pub basins: Vec<Basin<F>>,
+    pub hops: Vec<Hop<F>>,
+    pub n_accepted: u64,
+    pub x_best: Vec<F>,
+    pub e_best: F,
+}
+
+impl<F: McFloat> BasinHoppingResult<F> {
+    /// Basin the walk is in after each hop, starting with the initial one.
+    pub fn basin_sequence(&self) -> Vec<usize> {
+        let mut sequence: Vec<usize> = vec![0];
+        for hop in &self.hops {
+            let current: usize = *sequence.last().unwrap();
+            sequence.push(hop.to.filter(|_| hop.accepted).unwrap_or(current));
+        }
+        return sequence;
+    }
+}
+
+/// Basin hopping (Wales-Doye): n_step hops, each perturbing the current local
+/// minimum with the trial move of `proposal`/`x_delta`, descending into the
+/// local minimum of the perturbed point with `bh_minimizer` and accepting it
+/// with the Metropolis criterion at the temperature of the cooling schedule
+/// (step = hop). Minima closer than `bh_basin_radius` in every coordinate
+/// count as the same basin. With bounds, hops whose perturbation is rejected
+/// by the boundary policy or whose local minimum lies outside the box are
+/// recorded as rejected hops without a target basin.
+pub fn run_basin_hopping<F: McFloat, O: Objective<F> + ?Sized>(
+    params: AckleyMcParams<F>,
+    objective: &O,
+) -> BasinHoppingResult<F> {
+    if params.bh_minimizer == "gradient_descent" {
+        let mut grad: Vec<F> = vec![F::zero(); params.x_ini.len()];
+        assert!(
+            objective.gradient(&params.x_ini, &mut grad),
+            "{} has no gradient, needed by gradient_descent",
+            objective.name()
+        );
+    }
+    let minimize = |x: &[F]| -> (Vec<F>, F) {
+        let (step, max_iter, tol) = (params.bh_step, params.bh_local_steps, params.bh_tolerance);
+        if params.bh_minimizer == "gradient_descent" {
+            return gradient_descent(objective, x, step, max_iter, tol);
+        }
+        return nelder_mead(objective, x, step, max_iter, tol);
+    };
+
+    let schedule: Box<dyn CoolingSchedule<F>> = schedule_from_params(&params)
+        .unwrap_or_else(|| panic!("Unknown cooling schedule '{}'", params.schedule));
+    let (x_start, e_start) = minimize(&params.x_ini);
+    // the chain only provides the perturbations and the acceptance test
+    let mut chain: Chain<F, O> = Chain::new(&params, objective, &x_start, params.seed);
+
+    let bounds: Option<Bounds<F>> = Bounds::from_params(&params);
+
+    let mut basins: Vec<Basin<F>> = vec![Basin {
+        x: x_start.clone(),
+        energy: e_start,
+        n_visits: 1,
+    }];
+    let mut hops: Vec<Hop<F>> = Vec::with_capacity(params.n_step as usize);
+    let mut current: usize = 0;
+    let mut n_accepted: u64 = 0;
+
+    for hop in 0..params.n_step {
+        let temperature: F = schedule.temperature(hop);
+        let out_of_bounds = Hop {
+            hop,
+            from: current,
+            to: None,
+            temperature,
+            accepted: false,
+        };
+        let e_perturbed: F = chain.propose(temperature);
+        let x_perturbed: Vec<F> = chain.x.clone();
+        chain.reject();
+        if e_perturbed.is_infinite() {
+            hops.push(out_of_bounds);
+            continue;
+        }
+
+        let (x_min, e_min) = minimize(&x_perturbed);
+        if bounds.as_ref().is_some_and(|b| !b.contains(&x_min)) {
+            hops.push(out_of_bounds);
+            continue;
+        }
+        let to: usize = match basins
+            .iter()
+            .position(|b| same_basin(&b.x, &x_min, params.bh_basin_radius))
+        {
+            Some(k) => k,
+            None => {
+                basins.push(Basin {
+                    x: x_min.clone(),
+                    energy: e_min,
+                    n_visits: 0,
+                });
+                basins.len() - 1
+            }
+        };
+        basins[to].n_visits += 1;
+
+        // the hop is between basin minima, the perturbation's Hastings
+        // correction does not apply to it
+        let accepted: bool =
+            Metropolis.accept(chain.energy, e_min, temperature, F::zero(), &mut chain.rng);
+        hops.push(Hop {
+            hop,
+            from: current,
+            to: Some(to),
+            temperature,
+            accepted,
+        });
+        if accepted {
+            chain.set_state(&x_min);
+            current = to;
+            n_accepted += 1;
+        }
+    }
+
+    let best: usize = (0..basins.len())
+        .min_by(|&a, &b| basins[a].energy.total_cmp(&basins[b].energy))
+        .unwrap();
+    return BasinHoppingResult {
+        objective: objective.name(),
+        x_best: basins[best].x.clone(),
+        e_best: basins[best].energy,
+        basins,
+        hops,
+        n_accepted,
+    };
+}
+
+fn same_basin<F: McFloat>(a: &[F], b: &[F], radius: F) -> bool {
+    return a.iter().zip(b).all(|(&ai, &bi)| (ai - bi).abs() < radius);
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_local_minimizers() {
+    use crate::landscapes::Rosenbrock;
+
+    let (x, e) = nelder_mead(&Rosenbrock, &[-1.2f64, 1.0], 0.5, 5000, 1e-14);
+    assert!(e < 1e-8);
+    assert!((x[0] - 1.0).abs() < 1e-3 && (x[1] - 1.0).abs() < 1e-3);
+
+    let (x, e) = gradient_descent(&Rosenbrock, &[-1.2f64, 1.0], 0.1, 100_000, 1e-8);
+    assert!(e < 1e-8);
+    assert!((x[0] - 1.0).abs() < 1e-3 && (x[1] - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_basin_hopping_ackley() {
+    use crate::ackley_mc::{build_amcparams, Ackley};
+
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.mode = "basin_hopping".to_string();
+    params.ini_temp = 1.0;
+    params.final_temp = 1.0;
+    params.n_step = 50;
+    params.x_ini = vec![3.0, -2.0];
+    params.x_delta = 1.0;
+    params.proposal = "all".to_string();
+
+    let res = run_basin_hopping(params.clone(), &Ackley::default());
+    assert_eq!(res.hops.len(), 50);
+    assert!(res.basins.len() > 1);
+    assert_eq!(res.basin_sequence().len(), 51);
+    assert_eq!(res.basins.iter().map(|b| b.n_visits).sum::<u64>(), 51);
+    // Ackley's local minima sit near integer points, the global one at 0
+    assert!(res.e_best < 1e-3);
+
+    // hops leaving a tight box are recorded as rejected
+    params.lower = vec![2.0, -3.0];
+    params.upper = vec![4.0, -1.0];
+    let res = run_basin_hopping(params, &Ackley::default());
+    assert_eq!(res.hops.len(), 50);
+    assert!(res.hops.iter().any(|h| h.to.is_none()));
+    assert!(res.hops.iter().all(|h| h.to.is_some() || !h.accepted));
+    let n_landed: usize = res.hops.iter().filter(|h| h.to.is_some()).count();
+    assert_eq!(
+        res.basins.iter().map(|b| b.n_visits).sum::<u64>(),
+        n_landed as u64 + 1
+    );
+}
@@ -0,0 +1,138 @@
+use crate::ackley_mc::AckleyMcParams;
+use crate::float::McFloat;
+
+// -------------------- box constraints --------------------
+
+/// What happens to a trial coordinate outside its bounds, accepted by the
+/// `boundary` parameter: the move is rejected, the coordinate is mirrored at
+/// the violated bound, wrapped around periodically, or set to the bound.
+pub const BOUNDARY_POLICIES: [&str; 4] = ["reject", "reflect", "wrap", "clamp"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundaryPolicy {
+    Reject,
+    Reflect,
+    Wrap,
+    Clamp,
+}
+
+impl BoundaryPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name {
+            "reject" => Some(BoundaryPolicy::Reject),
+            "reflect" => Some(BoundaryPolicy::Reflect),
+            "wrap" => Some(BoundaryPolicy::Wrap),
+            "clamp" => Some(BoundaryPolicy::Clamp),
+            _ => None,
+        };
+    }
+}
+
+/// Per-dimension box [lower_i, upper_i] the walk is confined to.
+#[derive(Clone, Debug)]
+pub struct Bounds<F: McFloat> {
+    pub lower: Vec<F>,
+    pub upper: Vec<F>,
+    pub policy: BoundaryPolicy,
+}
+
+impl<F: McFloat> Bounds<F> {
+    /// The bounds set in `params`, `None` if the walk is unbounded.
+    pub fn from_params(params: &AckleyMcParams<F>) -> Option<Self> {
+        if params.lower.is_empty() {
+            return None;
+        }
+        let policy = BoundaryPolicy::from_name(&params.boundary)
+            .unwrap_or_else(|| panic!("Unknown boundary policy '{}'", params.boundary));
+        return Some(Bounds {
+            lower: params.lower.clone(),
+            upper: params.upper.clone(),
+            policy,
+        });
+    }
+
+    pub fn contains(&self, x: &[F]) -> bool {
+        return x
+            .iter()
+            .zip(self.lower.iter().zip(&self.upper))
+            .all(|(&xi, (&lo, &up))| xi >= lo && xi <= up);
+    }
+
+    /// Applies the policy to the coordinates the trial move changed (index,
+    /// old value). Returns false if the move has to be rejected.
+    pub fn apply(&self, x: &mut [F], changed: &[(usize, F)]) -> bool {
+        for &(i, _) in changed {
+            let (lo, up) = (self.lower[i], self.upper[i]);
+            if x[i] >= lo && x[i] <= up {
+                continue;
+            }
+            let width: F = up - lo;
+            x[i] = match self.policy {
+                BoundaryPolicy::Reject => return false,
+                BoundaryPolicy::Clamp => x[i].max(lo).min(up),
+                BoundaryPolicy::Wrap => lo + rem_euclid(x[i] - lo, width),
+                // mirroring repeatedly at both bounds is a triangle wave with period 2 width
+                BoundaryPolicy::Reflect => {
+                    let folded: F = rem_euclid(x[i] - lo, F::lit(2.0) * width);
+                    if folded <= width {
+                        lo + folded
+                    } else {
+                        up - (folded - width)
+                    }
+                }
+            };
+        }
+        return true;
+    }
+}
+
+/// a mod b in [0, b) for b > 0.
+#[inline]
+fn rem_euclid<F: McFloat>(a: F, b: F) -> F {
+    let r: F = a % b;
+    if r < F::zero() {
+        return r + b;
+    }
+    return r;
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_boundary_policies() {
+    let bounds = |policy| Bounds {
+        lower: vec![-1.0f64, 0.0],
+        upper: vec![1.0, 10.0],
+        policy,
+    };
+    let changed: Vec<(usize, f64)> = vec![(0, 0.0), (1, 5.0)];
+    let apply = |policy, x: [f64; 2]| {
+        let mut x: Vec<f64> = x.to_vec();
+        let in_bounds: bool = bounds(policy).apply(&mut x, &changed);
+        return (in_bounds, x);
+    };
+
+    assert_eq!(
+        apply(BoundaryPolicy::Reject, [0.5, 5.0]),
+        (true, vec![0.5, 5.0])
+    );
+    assert!(!apply(BoundaryPolicy::Reject, [1.5, 5.0]).0);
+    assert_eq!(
+        apply(BoundaryPolicy::Clamp, [1.5, -2.0]),
+        (true, vec![1.0, 0.0])
+    );
+    assert_eq!(
+        apply(BoundaryPolicy::Wrap, [1.5, 12.0]),
+        (true, vec![-0.5, 2.0])
+    );
+    assert_eq!(
+        apply(BoundaryPolicy::Reflect, [1.5, -2.0]),
+        (true, vec![0.5, 2.0])
+    );
+    // far outside: reflected at both bounds
+    assert_eq!(
+        apply(BoundaryPolicy::Reflect, [3.5, 25.0]),
+        (true, vec![-0.5, 5.0])
+    );
+    assert!(bounds(BoundaryPolicy::Reject).contains(&[1.0, 0.0]));
+}
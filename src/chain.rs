@@ -0,0 +1,322 @@
+use crate::acceptance::{AcceptanceRule, Metropolis};
+use crate::ackley_mc::{AckleyMcParams, StepAdapt};
+use crate::bounds::{BoundaryPolicy, Bounds};
+use crate::float::McFloat;
+use crate::gradient::{gradient_move_from_params, GradientMove};
+use crate::objective::{DeltaObjective, Objective};
+use crate::proposal::{proposal_from_params, Proposal};
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+// Robbins-Monro gain of the n-th step size update: n^(-ADAPT_DECAY)
+static ADAPT_DECAY: f64 = 0.6;
+
+// -------------------- markov chain --------------------
+
+/// State of one Markov chain on an objective and the machinery to move it:
+/// proposal or gradient move, running sums for incremental evaluation, step
+/// sizes and its own random number generator. The drivers (annealing,
+/// replica exchange, ...) decide on temperatures and what to record.
+///
+/// A step is `propose` (applies the trial move to `x` in place), an
+/// acceptance decision, e.g. `metropolis`, and `accept` or `reject` (undoes
+/// the move), so no step allocates.
+pub struct Chain<'a, F: McFloat, O: Objective<F> + ?Sized> {
+    pub objective: &'a O,
+    pub rng: ChaCha8Rng,
+    pub x: Vec<F>,
+    pub energy: F,
+    /// Best point seen, including rejected trial points.
+    pub x_best: Vec<F>,
+    pub e_best: F,
+    pub n_steps: u64,
+    pub n_accepted: u64,
+    /// One step size or one per dimension.
+    pub x_deltas: Vec<F>,
+    n_adapted: Vec<u64>,
+    proposal: Box<dyn Proposal<F>>,
+    gradient_move: Option<Box<dyn GradientMove<F>>>,
+    bounds: Option<Bounds<F>>,
+    // false if the current trial move left the bounds and must be rejected
+    in_bounds: bool,
+    // ini_temp, if the proposal width scales with the temperature
+    temp_scaled: Option<F>,
+    // (index, old value) of the coordinates changed by the current trial move
+    changed: Vec<(usize, F)>,
+    // log of the Hastings correction of the current trial move
+    log_correction: F,
+    // running sums for O(1) single-coordinate updates, if the objective supports them
+    delta: Option<&'a dyn DeltaObjective<F>>,
+    delta_refresh: u64,
+    sums: Vec<F>,
+    trial_sums: Vec<F>,
+}
+
+impl<'a, F: McFloat, O: Objective<F> + ?Sized> Chain<'a, F, O> {
+    /// Chain starting at `x_ini` with the move settings of `params`.
+    pub fn new(params: &AckleyMcParams<F>, objective: &'a O, x_ini: &[F], seed: u64) -> Self {
+        if let Some(dim) = objective.dim() {
+            assert_eq!(
+                x_ini.len(),
+                dim,
+                "{} is only defined in {} dimensions",
+                objective.name(),
+                dim
+            );
+        }
+        let proposal: Box<dyn Proposal<F>> = proposal_from_params(params)
+            .unwrap_or_else(|| panic!("Unknown proposal '{}'", params.proposal));
+
+        // MALA/HMC replace the proposal if selected
+        let gradient_move = gradient_move_from_params(params);
+        let bounds: Option<Bounds<F>> = Bounds::from_params(params);
+        if gradient_move.is_some() {
+            // the Hastings correction of MALA/HMC is only valid for the
+            // unmapped trial point, so out of bounds moves must be rejected
+            assert!(
+                bounds
+                    .as_ref()
+                    .is_none_or(|b| b.policy == BoundaryPolicy::Reject),
+                "The {} sampler needs boundary reject",
+                params.sampler
+            );
+            let mut grad: Vec<F> = vec![F::zero(); x_ini.len()];
+            assert!(
+                objective.gradient(x_ini, &mut grad),
+                "{} has no gradient, needed by the {} sampler",
+                objective.name(),
+                params.sampler
+            );
+        }
+
+        let x_deltas: Vec<F> = if params.step_adapt == StepAdapt::PerDim {
+            vec![params.x_delta; x_ini.len()]
+        } else {
+            vec![params.x_delta]
+        };
+
+        let delta = if params.delta_eval {
+            objective.delta()
+        } else {
+            None
+        };
+        let sums: Vec<F> = delta.map_or(vec![], |d| d.sums(x_ini));
+        let energy: F = objective.evaluate(x_ini);
+
+        return Chain {
+            objective,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            x: x_ini.to_vec(),
+            energy,
+            x_best: x_ini.to_vec(),
+            e_best: energy,
+            n_steps: 0,
+            n_accepted: 0,
+            n_adapted: vec![0; x_deltas.len()],
+            x_deltas,
+            proposal,
+            gradient_move,
+            bounds,
+            in_bounds: true,
+            temp_scaled: if params.proposal_temp_scaled {
+                Some(params.ini_temp)
+            } else {
+                None
+            },
+            changed: Vec::with_capacity(x_ini.len()),
+            log_correction: F::zero(),
+            delta,
+            delta_refresh: params.delta_refresh,
+            trial_sums: sums.clone(),
+            sums,
+        };
+    }
+
+    /// Applies a trial move for `temperature` to `x` and returns its energy,
+    /// infinite if it left the bounds and has to be rejected. Also updates the
+    /// best point.
+    pub fn propose(&mut self, temperature: F) -> F {
+        // Szu-Hartley fast annealing: the proposal width shrinks with the temperature.
+        let scale: F = match self.temp_scaled {
+            Some(ini_temp) => temperature / ini_temp,
+            None => F::one(),
+        };
+        let objective = self.objective;
+        let gradient = |x: &[F], grad: &mut [F]| {
+            objective.gradient(x, grad);
+        };
+
+        self.changed.clear();
+        self.log_correction = match self.gradient_move.as_mut() {
+            Some(g) => g.propose(
+                &gradient,
+                &mut self.x,
+                &self.x_deltas,
+                temperature,
+                &mut self.rng,
+                &mut self.changed,
+            ),
+            None => {
+                self.proposal.propose(
+                    &mut self.x,
+                    &self.x_deltas,
+                    scale,
+                    &mut self.rng,
+                    &mut self.changed,
+                );
+                F::zero()
+            }
+        };
+
+        // A rejected move is never evaluated, so it cannot become the best point.
+        self.in_bounds = match &self.bounds {
+            Some(b) => b.apply(&mut self.x, &self.changed),
+            None => true,
+        };
+        if !self.in_bounds {
+            return F::infinity();
+        }
+
+        let e_trial: F = match self.delta {
+            Some(d) => {
+                self.trial_sums.copy_from_slice(&self.sums);
+                for &(i, x_old) in &self.changed {
+                    d.update_sums(&mut self.trial_sums, i, x_old, self.x[i]);
+                }
+                d.energy_from_sums(&self.trial_sums, self.x.len())
+            }
+            None => objective.evaluate(&self.x),
+        };
+
+        if e_trial < self.e_best {
+            self.e_best = e_trial;
+            self.x_best.copy_from_slice(&self.x);
+        }
+        return e_trial;
+    }
+
+    /// Metropolis(-Hastings) criterion for the current trial move.
+    pub fn metropolis(&mut self, e_trial: F, temperature: F) -> bool {
+        return self.accept_with(&mut Metropolis, e_trial, temperature);
+    }
+
+    /// Decides on the current trial move with `rule`.
+    pub fn accept_with(
+        &mut self,
+        rule: &mut dyn AcceptanceRule<F>,
+        e_trial: F,
+        temperature: F,
+    ) -> bool {
+        return self.accept_energies(rule, self.energy, e_trial, temperature);
+    }
+
+    /// Like `accept_with`, but compares `e_from` and `e_to` instead of the
+    /// energies of the current and the trial state, e.g. transformed ones.
+    pub fn accept_energies(
+        &mut self,
+        rule: &mut dyn AcceptanceRule<F>,
+        e_from: F,
+        e_to: F,
+        temperature: F,
+    ) -> bool {
+        if !self.in_bounds {
+            return false;
+        }
+        return rule.accept(
+            e_from,
+            e_to,
+            temperature,
+            self.log_correction,
+            &mut self.rng,
+        );
+    }
+
+    /// Log of the Hastings correction of the current trial move (0 for
+    /// symmetric proposals).
+    pub fn log_correction(&self) -> F {
+        return self.log_correction;
+    }
+
+    /// Coordinates changed by the current trial move, with their old values.
+    pub fn changed(&self) -> &[(usize, F)] {
+        return &self.changed;
+    }
+
+    /// Keeps the current trial move, which has energy `e_trial`.
+    pub fn accept(&mut self, e_trial: F) {
+        self.energy = e_trial;
+        std::mem::swap(&mut self.sums, &mut self.trial_sums);
+        self.n_accepted += 1;
+        self.end_step();
+    }
+
+    /// Undoes the current trial move.
+    pub fn reject(&mut self) {
+        for &(i, x_old) in &self.changed {
+            self.x[i] = x_old;
+        }
+        self.end_step();
+    }
+
+    /// Propose, Metropolis criterion, accept or reject; returns whether the
+    /// move was accepted.
+    pub fn step(&mut self, temperature: F) -> bool {
+        let e_trial: F = self.propose(temperature);
+        let accept: bool = self.metropolis(e_trial, temperature);
+        if accept {
+            self.accept(e_trial);
+        } else {
+            self.reject();
+        }
+        return accept;
+    }
+
+    fn end_step(&mut self) {
+        self.n_steps += 1;
+        self.proposal.observe(&self.x);
+
+        // Recompute the running sums from scratch to stop rounding errors from piling up.
+        if let Some(d) = self.delta {
+            if self.n_steps.is_multiple_of(self.delta_refresh) {
+                self.sums = d.sums(&self.x);
+                self.energy = d.energy_from_sums(&self.sums, self.x.len());
+            }
+        }
+    }
+
+    /// Robbins-Monro: log(x_delta) += gain * (accepted - target acceptance),
+    /// per dimension for every coordinate the last move changed.
+    pub fn adapt_step_size(&mut self, accepted: bool, target_accept: F) {
+        let accepted: F = if accepted { F::one() } else { F::zero() };
+        let n_deltas: usize = if self.x_deltas.len() == 1 {
+            1
+        } else {
+            self.changed.len()
+        };
+        for &(i, _) in &self.changed[..n_deltas] {
+            let i: usize = if self.x_deltas.len() == 1 { 0 } else { i };
+            self.n_adapted[i] += 1;
+            let gain: F = F::from_u64(self.n_adapted[i])
+                .unwrap()
+                .powf(-F::lit(ADAPT_DECAY));
+            self.x_deltas[i] *= (gain * (accepted - target_accept)).exp();
+        }
+    }
+
+    /// Jumps to `x` (e.g. a restart), recomputing energy and running sums.
+    pub fn set_state(&mut self, x: &[F]) {
+        self.x.copy_from_slice(x);
+        self.energy = self.objective.evaluate(&self.x);
+        if let Some(d) = self.delta {
+            self.sums = d.sums(&self.x);
+        }
+    }
+
+    /// Exchanges the states (and running sums) of two chains on the same
+    /// objective, keeping everything else, e.g. for replica exchange.
+    pub fn swap_states(&mut self, other: &mut Self) {
+        std::mem::swap(&mut self.x, &mut other.x);
+        std::mem::swap(&mut self.energy, &mut other.energy);
+        std::mem::swap(&mut self.sums, &mut other.sums);
+    }
+}
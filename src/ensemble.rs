@@ -0,0 +1,171 @@
+use crate::ackley_mc::{run_mc, AckleyMcParams, AckleyMcResult};
+use crate::float::McFloat;
+use crate::landscapes::objective_from_params;
+use crate::objective::Objective;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// -------------------- multi-start ensemble --------------------
+
+/// Result of `ens_runs` independent chains on the same parameters; run k used
+/// seed `seeds[k]`.
+#[derive(Clone, Debug)]
+pub struct EnsembleResult<F: McFloat = f32> {
+    pub objective: String,
+    pub seeds: Vec<u64>,
+    pub runs: Vec<AckleyMcResult<F>>,
+    /// A run succeeded if its best energy is at most this: the global minimum
+    /// (0 if unknown) plus `ens_success`.
+    pub e_success: F,
+}
+
+impl<F: McFloat> EnsembleResult<F> {
+    /// Best energies of all runs, sorted ascending.
+    pub fn sorted_e_best(&self) -> Vec<F> {
+        let mut e_best: Vec<F> = self.runs.iter().map(|r| r.e_best).collect();
+        e_best.sort_by(|a, b| a.total_cmp(b));
+        return e_best;
+    }
+
+    /// q-quantile (0 <= q <= 1) of the best energies, interpolated linearly
+    /// between the order statistics.
+    pub fn e_best_quantile(&self, q: f64) -> F {
+        let e_best: Vec<F> = self.sorted_e_best();
+        let pos: f64 = q * (e_best.len() - 1) as f64;
+        let (k, frac) = (pos.floor() as usize, F::lit(pos.fract()));
+        if k + 1 == e_best.len() {
+            return e_best[k];
+        }
+        return e_best[k] + frac * (e_best[k + 1] - e_best[k]);
+    }
+
+    pub fn is_success(&self, run: usize) -> bool {
+        return self.runs[run].e_best <= self.e_success;
+    }
+
+    pub fn success_rate(&self) -> f64 {
+        let n_success: usize = (0..self.runs.len()).filter(|&k| self.is_success(k)).count();
+        return n_success as f64 / self.runs.len() as f64;
+    }
+
+    /// Index of the run that found the lowest energy.
+    pub fn best_run(&self) -> usize {
+        let mut best: usize = 0;
+        for (k, run) in self.runs.iter().enumerate() {
+            if run.e_best < self.runs[best].e_best {
+                best = k;
+            }
+        }
+        return best;
+    }
+}
+
+/// Number of threads `n_jobs` independent runs are spread over: `ens_threads`,
+/// or the number of available cores if it is 0, never more than `n_jobs`.
+pub fn thread_count<F: McFloat>(params: &AckleyMcParams<F>, n_jobs: usize) -> usize {
+    let n_threads: usize = if params.ens_threads > 0 {
+        params.ens_threads
+    } else {
+        thread::available_parallelism().map_or(1, |n| n.get())
+    };
+    return n_threads.min(n_jobs).max(1);
+}
+
+/// Calls `job(k, objective)` for k = 0..n_jobs on `thread_count` threads and
+/// returns the results in the order of k. Objectives are not shared between
+/// threads, each thread builds its own from `params`.
+pub fn run_parallel<F, R, J>(params: &AckleyMcParams<F>, n_jobs: usize, job: J) -> Vec<R>
+where
+    F: McFloat,
+    R: Send,
+    J: Fn(usize, &dyn Objective<F>) -> R + Sync,
+{
+    let next_job = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new((0..n_jobs).map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..thread_count(params, n_jobs) {
+            scope.spawn(|| {
+                let objective: Box<dyn Objective<F>> = objective_from_params(params)
+                    .unwrap_or_else(|| panic!("Unknown function '{}'", params.function));
+                loop {
+                    let k: usize = next_job.fetch_add(1, Ordering::Relaxed);
+                    if k >= n_jobs {
+                        break;
+                    }
+                    let res: R = job(k, objective.as_ref());
+                    results.lock().unwrap()[k] = Some(res);
+                }
+            });
+        }
+    });
+
+    return results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.unwrap())
+        .collect();
+}
+
+/// Runs `ens_runs` independent `run_mc` chains with seeds seed, seed + 1, ...
+/// (so run 0 repeats the single chain of mode mc) in parallel. The result
+/// does not depend on the number of threads.
+pub fn run_ensemble<F: McFloat>(params: AckleyMcParams<F>) -> EnsembleResult<F> {
+    let seeds: Vec<u64> = (0..params.ens_runs as u64)
+        .map(|k| params.seed.wrapping_add(k))
+        .collect();
+    let runs: Vec<AckleyMcResult<F>> = run_parallel(&params, params.ens_runs, |k, objective| {
+        let mut run_params: AckleyMcParams<F> = params.clone();
+        run_params.seed = seeds[k];
+        return run_mc(run_params, objective);
+    });
+
+    let objective = objective_from_params(&params).unwrap();
+    let e_min: F = objective
+        .global_minimum(params.x_ini.len())
+        .map_or(F::zero(), |(_, e_min)| e_min);
+    return EnsembleResult {
+        objective: objective.name(),
+        seeds,
+        runs,
+        e_success: e_min + params.ens_success,
+    };
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_ensemble_matches_single_runs() {
+    use crate::ackley_mc::{build_amcparams, run_ackley_mc};
+
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.mode = "ensemble".to_string();
+    params.n_step = 500;
+    params.ini_temp = 1.0;
+    params.final_temp = 0.01;
+    params.x_ini = vec![2.0, -1.5];
+    params.x_delta = 0.5;
+    params.ens_runs = 6;
+    params.ens_threads = 4;
+    params.ens_success = 0.5;
+
+    let res = run_ensemble(params.clone());
+    assert_eq!(res.seeds, (3141..3147).collect::<Vec<u64>>());
+    for (k, run) in res.runs.iter().enumerate() {
+        let mut single: AckleyMcParams<f64> = params.clone();
+        single.seed = res.seeds[k];
+        let expected = run_ackley_mc(single);
+        assert_eq!(run.e_best, expected.e_best);
+        assert_eq!(run.n_accepted, expected.n_accepted);
+    }
+
+    let e_best: Vec<f64> = res.sorted_e_best();
+    assert_eq!(res.e_best_quantile(0.0), e_best[0]);
+    assert_eq!(res.e_best_quantile(1.0), e_best[5]);
+    assert_eq!(res.e_best_quantile(0.5), 0.5 * (e_best[2] + e_best[3]));
+    assert_eq!(res.runs[res.best_run()].e_best, e_best[0]);
+    let n_success: usize = e_best.iter().filter(|&&e| e <= 0.5).count();
+    assert_eq!(res.success_rate(), n_success as f64 / 6.0);
+}
@@ -1,37 +1,255 @@
-use crate::ackley_mc::build_amcparams;
 use crate::{AckleyMcParams, AckleyMcResult};
+use ackley_mc::acceptance::ACCEPTANCE_NAMES;
+use ackley_mc::ackley_mc::{
+    build_amcparams, ReheatPolicy, StepAdapt, MODES, REHEAT_POLICIES, STEP_ADAPT_MODES,
+};
+use ackley_mc::basin_hopping::{BasinHoppingResult, MINIMIZER_NAMES};
+use ackley_mc::bounds::BOUNDARY_POLICIES;
+use ackley_mc::ensemble::EnsembleResult;
+use ackley_mc::float::McFloat;
+use ackley_mc::gradient::SAMPLER_NAMES;
+use ackley_mc::landscapes::FUNCTION_NAMES;
+use ackley_mc::population::PopulationResult;
+use ackley_mc::proposal::{PROPOSAL_NAMES, STEP_DIST_NAMES};
+use ackley_mc::schedule::SCHEDULE_NAMES;
+use ackley_mc::sweep::{
+    is_sweep_value, parse_sweep_values, set_swept_value, sweep_points, SweepAxis, SweepResult,
+    SWEEP_KEYS,
+};
+use ackley_mc::trajectory::{CsvSink, TrajectorySink};
+use ackley_mc::transform::random_rotation;
+use ackley_mc::wang_landau::{WangLandauResult, WL_SCHEDULES};
 use std::fs;
-use std::fs::read_to_string;
+use std::fs::{read_to_string, File};
 use std::io::{BufWriter, Write};
 
 // -------------------- parameter file parser --------------------
 
-pub fn parse_file(filename: String) -> AckleyMcParams {
+/// Floating point precisions accepted by the `precision` key.
+pub const PRECISIONS: [&str; 2] = ["f32", "f64"];
+
+/// Reads only the `precision` key ("f32" if absent), which decides the float
+/// type `parse_file` is instantiated with.
+pub fn parse_precision(filename: String) -> String {
     let file_contents = read_to_string(filename.clone())
-        .expect(format!("Err: Cannot read parameter file '{}'", filename).as_str());
+        .unwrap_or_else(|_| panic!("Err: Cannot read parameter file '{}'", filename));
+
+    let mut precision = "f32".to_string();
+    for l in file_contents.lines() {
+        let parts: Vec<&str> = l.split(' ').collect();
+        if "precision".eq(parts[0]) {
+            precision = parts[1].to_string();
+        }
+    }
+    assert!(
+        PRECISIONS.contains(&precision.as_str()),
+        "Unknown precision '{precision}', expected one of {PRECISIONS:?}"
+    );
+    return precision;
+}
+
+/// Where the visited states go: kept in memory (needed for plots), streamed to
+/// a csv or binary file, or only summarised.
+pub const TRAJECTORY_MODES: [&str; 4] = ["memory", "csv", "binary", "summary"];
+
+/// Reads and validates the parameter file. `burn_in` and `thin` come from the
+/// command line and take precedence over the file.
+pub fn parse_file<F: McFloat>(
+    filename: String,
+    burn_in: Option<u64>,
+    thin: Option<u64>,
+) -> AckleyMcParams<F> {
+    let file_contents = read_to_string(filename.clone())
+        .unwrap_or_else(|_| panic!("Err: Cannot read parameter file '{}'", filename));
     let lines = file_contents.lines();
 
     let mut amc_params = build_amcparams();
+    let mut rotation: String = String::new();
+    let mut rotation_seed: Option<u64> = None;
     for l in lines {
         let parts: Vec<&str> = l.split(' ').collect();
         let key = parts[0];
 
-        if "ini_temp".eq(key) {
-            amc_params.ini_temp = parts[1].parse::<f32>().expect("Bad line (ini_temp)");
+        if SWEEP_KEYS.contains(&key) && is_sweep_value(parts[1]) {
+            let values: Vec<f64> =
+                parse_sweep_values(parts[1]).unwrap_or_else(|| panic!("Bad line ({key})"));
+            amc_params.sweep.push(SweepAxis {
+                key: key.to_string(),
+                values,
+            });
+        } else if "mode".eq(key) {
+            amc_params.mode = parts[1].parse::<String>().expect("Bad line (mode)");
+        } else if "function".eq(key) {
+            amc_params.function = parts[1].parse::<String>().expect("Bad line (function)");
+        } else if "ack_a".eq(key) {
+            amc_params.ack_a = parse_float(parts[1], "ack_a");
+        } else if "ack_b".eq(key) {
+            amc_params.ack_b = parse_float(parts[1], "ack_b");
+        } else if "ack_c".eq(key) {
+            amc_params.ack_c = parse_float(parts[1], "ack_c");
+        } else if "shift".eq(key) {
+            amc_params.shift = parts[1]
+                .split(',')
+                .map(|s| parse_float(s, "shift"))
+                .collect::<Vec<F>>();
+        } else if "rotation".eq(key) {
+            rotation = parts[1].parse::<String>().expect("Bad line (rotation)");
+        } else if "rotation_seed".eq(key) {
+            rotation_seed = Some(parts[1].parse::<u64>().expect("Bad line (rotation_seed)"));
+        } else if "ini_temp".eq(key) {
+            amc_params.ini_temp = parse_float(parts[1], "ini_temp");
         } else if "final_temp".eq(key) {
-            amc_params.final_temp = parts[1].parse::<f32>().expect("Bad line (final_temp)");
+            amc_params.final_temp = parse_float(parts[1], "final_temp");
+        } else if "schedule".eq(key) {
+            amc_params.schedule = parts[1].parse::<String>().expect("Bad line (schedule)");
+        } else if "schedule_stairs".eq(key) {
+            amc_params.schedule_stairs =
+                parts[1].parse::<u64>().expect("Bad line (schedule_stairs)");
+        } else if "schedule_window".eq(key) {
+            amc_params.schedule_window =
+                parts[1].parse::<u64>().expect("Bad line (schedule_window)");
+        } else if "schedule_lambda".eq(key) {
+            amc_params.schedule_lambda = parse_float(parts[1], "schedule_lambda");
+        } else if "schedule_file".eq(key) {
+            amc_params.schedule_table = read_schedule_table(parts[1].to_string());
+        } else if "reheat".eq(key) {
+            amc_params.reheat = ReheatPolicy::from_name(parts[1]).unwrap_or_else(|| {
+                panic!(
+                    "Unknown reheat policy '{}', expected one of {:?}",
+                    parts[1], REHEAT_POLICIES
+                )
+            });
+        } else if "reheat_patience".eq(key) {
+            amc_params.reheat_patience =
+                parts[1].parse::<u64>().expect("Bad line (reheat_patience)");
+        } else if "reheat_fraction".eq(key) {
+            amc_params.reheat_fraction = parse_float(parts[1], "reheat_fraction");
+        } else if "pt_replicas".eq(key) {
+            amc_params.pt_replicas = parts[1].parse::<usize>().expect("Bad line (pt_replicas)");
+        } else if "pt_temps".eq(key) {
+            amc_params.pt_temps = parts[1]
+                .split(',')
+                .map(|s| parse_float(s, "pt_temps"))
+                .collect::<Vec<F>>();
+        } else if "pt_swap_interval".eq(key) {
+            amc_params.pt_swap_interval = parts[1]
+                .parse::<u64>()
+                .expect("Bad line (pt_swap_interval)");
+        } else if "pa_size".eq(key) {
+            amc_params.pa_size = parts[1].parse::<usize>().expect("Bad line (pa_size)");
+        } else if "pa_interval".eq(key) {
+            amc_params.pa_interval = parts[1].parse::<u64>().expect("Bad line (pa_interval)");
+        } else if "wl_e_min".eq(key) {
+            amc_params.wl_e_min = parse_float(parts[1], "wl_e_min");
+        } else if "wl_e_max".eq(key) {
+            amc_params.wl_e_max = parse_float(parts[1], "wl_e_max");
+        } else if "wl_bins".eq(key) {
+            amc_params.wl_bins = parts[1].parse::<usize>().expect("Bad line (wl_bins)");
+        } else if "wl_flatness".eq(key) {
+            amc_params.wl_flatness = parse_float(parts[1], "wl_flatness");
+        } else if "wl_check_interval".eq(key) {
+            amc_params.wl_check_interval = parts[1]
+                .parse::<u64>()
+                .expect("Bad line (wl_check_interval)");
+        } else if "wl_schedule".eq(key) {
+            amc_params.wl_schedule = parts[1].parse::<String>().expect("Bad line (wl_schedule)");
+        } else if "wl_ln_f_final".eq(key) {
+            amc_params.wl_ln_f_final = parse_float(parts[1], "wl_ln_f_final");
+        } else if "bh_minimizer".eq(key) {
+            amc_params.bh_minimizer = parts[1].parse::<String>().expect("Bad line (bh_minimizer)");
+        } else if "bh_local_steps".eq(key) {
+            amc_params.bh_local_steps = parts[1].parse::<u64>().expect("Bad line (bh_local_steps)");
+        } else if "bh_tolerance".eq(key) {
+            amc_params.bh_tolerance = parse_float(parts[1], "bh_tolerance");
+        } else if "bh_step".eq(key) {
+            amc_params.bh_step = parse_float(parts[1], "bh_step");
+        } else if "bh_basin_radius".eq(key) {
+            amc_params.bh_basin_radius = parse_float(parts[1], "bh_basin_radius");
+        } else if "ens_runs".eq(key) {
+            amc_params.ens_runs = parts[1].parse::<usize>().expect("Bad line (ens_runs)");
+        } else if "ens_threads".eq(key) {
+            amc_params.ens_threads = parts[1].parse::<usize>().expect("Bad line (ens_threads)");
+        } else if "ens_success".eq(key) {
+            amc_params.ens_success = parse_float(parts[1], "ens_success");
         } else if "n_step".eq(key) {
             amc_params.n_step = parts[1].parse::<u64>().expect("Bad line (n_step)");
         } else if "x_ini".eq(key) {
-            let vals: Vec<f32> = parts[1]
+            let vals: Vec<F> = parts[1]
                 .split(",")
-                .filter_map(|s| Option::from(s.parse::<f32>().expect("Bad line (x_ini)")))
-                .collect::<Vec<f32>>();
+                .filter_map(|s| Option::from(parse_float::<F>(s, "x_ini")))
+                .collect::<Vec<F>>();
             amc_params.x_ini = vals;
         } else if "x_delta".eq(key) {
-            amc_params.x_delta = parts[1].parse::<f32>().expect("Bad line (x_delta)");
+            amc_params.x_delta = parse_float(parts[1], "x_delta");
+        } else if "sampler".eq(key) {
+            amc_params.sampler = parts[1].parse::<String>().expect("Bad line (sampler)");
+        } else if "hmc_steps".eq(key) {
+            amc_params.hmc_steps = parts[1].parse::<u64>().expect("Bad line (hmc_steps)");
+        } else if "acceptance".eq(key) {
+            amc_params.acceptance = parts[1].parse::<String>().expect("Bad line (acceptance)");
+        } else if "tsallis_q_a".eq(key) {
+            amc_params.tsallis_q_a = parse_float(parts[1], "tsallis_q_a");
+        } else if "tsallis_q_v".eq(key) {
+            amc_params.tsallis_q_v = parse_float(parts[1], "tsallis_q_v");
+        } else if "deluge_rain".eq(key) {
+            amc_params.deluge_rain = parse_float(parts[1], "deluge_rain");
+        } else if "stun".eq(key) {
+            amc_params.stun = parts[1].parse::<bool>().expect("Bad line (stun)");
+        } else if "stun_gamma".eq(key) {
+            amc_params.stun_gamma = parse_float(parts[1], "stun_gamma");
+        } else if "lower".eq(key) {
+            amc_params.lower = parts[1]
+                .split(',')
+                .map(|s| parse_float(s, "lower"))
+                .collect::<Vec<F>>();
+        } else if "upper".eq(key) {
+            amc_params.upper = parts[1]
+                .split(',')
+                .map(|s| parse_float(s, "upper"))
+                .collect::<Vec<F>>();
+        } else if "boundary".eq(key) {
+            amc_params.boundary = parts[1].parse::<String>().expect("Bad line (boundary)");
+        } else if "proposal".eq(key) {
+            amc_params.proposal = parts[1].parse::<String>().expect("Bad line (proposal)");
+        } else if "proposal_step".eq(key) {
+            amc_params.proposal_step = parts[1]
+                .parse::<String>()
+                .expect("Bad line (proposal_step)");
+        } else if "proposal_subset".eq(key) {
+            amc_params.proposal_subset = parts[1]
+                .parse::<usize>()
+                .expect("Bad line (proposal_subset)");
+        } else if "proposal_temp_scaled".eq(key) {
+            amc_params.proposal_temp_scaled = parts[1]
+                .parse::<bool>()
+                .expect("Bad line (proposal_temp_scaled)");
+        } else if "am_start".eq(key) {
+            amc_params.am_start = parts[1].parse::<u64>().expect("Bad line (am_start)");
+        } else if "am_epsilon".eq(key) {
+            amc_params.am_epsilon = parse_float(parts[1], "am_epsilon");
+        } else if "step_adapt".eq(key) {
+            amc_params.step_adapt = StepAdapt::from_name(parts[1]).unwrap_or_else(|| {
+                panic!(
+                    "Unknown step size adaptation '{}', expected one of {:?}",
+                    parts[1], STEP_ADAPT_MODES
+                )
+            });
+        } else if "target_accept".eq(key) {
+            amc_params.target_accept = parse_float(parts[1], "target_accept");
+        } else if "delta_eval".eq(key) {
+            amc_params.delta_eval = parts[1].parse::<bool>().expect("Bad line (delta_eval)");
+        } else if "delta_refresh".eq(key) {
+            amc_params.delta_refresh = parts[1].parse::<u64>().expect("Bad line (delta_refresh)");
+        } else if "trajectory".eq(key) {
+            amc_params.trajectory = parts[1].parse::<String>().expect("Bad line (trajectory)");
+        } else if "burn_in".eq(key) {
+            amc_params.burn_in = parts[1].parse::<u64>().expect("Bad line (burn_in)");
+        } else if "thin".eq(key) {
+            amc_params.thin = parts[1].parse::<u64>().expect("Bad line (thin)");
         } else if "seed".eq(key) {
             amc_params.seed = parts[1].parse::<u64>().expect("Bad line (seed)");
+        } else if "precision".eq(key) {
+            // Already handled by parse_precision.
         } else if "foutname".eq(key) {
             amc_params.foutname = parts[1].parse::<String>().expect("Bad line (foutname)");
         } else {
@@ -39,25 +257,293 @@ pub fn parse_file(filename: String) -> AckleyMcParams {
         }
     }
 
+    if let Some(burn_in) = burn_in {
+        amc_params.burn_in = burn_in;
+    }
+    if let Some(thin) = thin {
+        amc_params.thin = thin;
+    }
+
+    // The rotation matrix size depends on x_ini, so it is built after all keys are read.
+    if rotation == "random" {
+        let seed = rotation_seed.unwrap_or(amc_params.seed);
+        amc_params.rotation = random_rotation(amc_params.x_ini.len(), seed);
+    } else if !rotation.is_empty() && rotation != "none" {
+        amc_params.rotation = read_matrix(rotation);
+    }
+
+    // The single-run parameters hold the first value of each swept parameter.
+    for axis in amc_params.sweep.clone() {
+        set_swept_value(&mut amc_params, &axis.key, axis.values[0]);
+    }
+
+    // A single bound applies to every dimension.
+    let dim: usize = amc_params.x_ini.len();
+    if amc_params.lower.len() == 1 {
+        amc_params.lower = vec![amc_params.lower[0]; dim];
+    }
+    if amc_params.upper.len() == 1 {
+        amc_params.upper = vec![amc_params.upper[0]; dim];
+    }
+
     amc_params = validate_amc_params(amc_params);
     return amc_params;
 }
 
-fn validate_amc_params(mut params: AckleyMcParams) -> AckleyMcParams {
+fn parse_float<F: McFloat>(s: &str, key: &str) -> F {
+    return s
+        .parse::<F>()
+        .unwrap_or_else(|_| panic!("Bad line ({key})"));
+}
+
+fn validate_amc_params<F: McFloat>(mut params: AckleyMcParams<F>) -> AckleyMcParams<F> {
     assert!(params.n_step > 0);
-    assert!(params.ini_temp > 0.0);
-    assert!(params.x_delta > 0.0);
+    assert!(params.ini_temp > F::zero());
+    assert!(params.x_delta > F::zero());
+    assert!(params.delta_refresh > 0);
+    assert!(
+        SAMPLER_NAMES.contains(&params.sampler.as_str()),
+        "Unknown sampler '{}', expected one of {:?}",
+        params.sampler,
+        SAMPLER_NAMES
+    );
+    assert!(params.hmc_steps > 0);
+    assert!(
+        ACCEPTANCE_NAMES.contains(&params.acceptance.as_str()),
+        "Unknown acceptance rule '{}', expected one of {:?}",
+        params.acceptance,
+        ACCEPTANCE_NAMES
+    );
+    assert!(
+        params.acceptance == "metropolis" || ["mc", "ensemble"].contains(&params.mode.as_str()),
+        "The {} acceptance rule is only available in modes mc and ensemble",
+        params.acceptance
+    );
+    assert!(
+        !params.stun || ["mc", "ensemble"].contains(&params.mode.as_str()),
+        "Stochastic tunneling is only available in modes mc and ensemble"
+    );
+    assert!(params.stun_gamma > F::zero());
+    assert!(params.tsallis_q_v > F::one());
+    assert!(params.deluge_rain >= F::zero());
+    assert!(
+        PROPOSAL_NAMES.contains(&params.proposal.as_str()),
+        "Unknown proposal '{}', expected one of {:?}",
+        params.proposal,
+        PROPOSAL_NAMES
+    );
+    assert!(
+        STEP_DIST_NAMES.contains(&params.proposal_step.as_str()),
+        "Unknown proposal step distribution '{}', expected one of {:?}",
+        params.proposal_step,
+        STEP_DIST_NAMES
+    );
+    assert!(
+        params.proposal_subset > 0 && params.proposal_subset <= params.x_ini.len(),
+        "proposal_subset must be between 1 and the dimension"
+    );
+    assert!(params.am_epsilon > F::zero());
+    assert!(params.target_accept > F::zero() && params.target_accept < F::one());
+    assert!(
+        params.step_adapt == StepAdapt::None || params.burn_in > 0,
+        "Step size adaptation needs a burn-in"
+    );
+    assert!(params.thin > 0);
+    assert!(
+        params.burn_in <= params.n_step,
+        "burn_in must not exceed n_step"
+    );
+    assert!(
+        SCHEDULE_NAMES.contains(&params.schedule.as_str()),
+        "Unknown cooling schedule '{}', expected one of {:?}",
+        params.schedule,
+        SCHEDULE_NAMES
+    );
+    assert!(params.schedule_stairs > 0);
+    assert!(params.schedule_window > 1);
+    assert!(params.schedule_lambda > F::zero());
+    assert!(
+        params
+            .schedule_table
+            .iter()
+            .all(|&(_, temp)| temp > F::zero()),
+        "Schedule table temperatures must be positive"
+    );
+    assert!(
+        params.schedule_table.windows(2).all(|w| w[0].0 < w[1].0),
+        "Schedule table steps must be strictly increasing"
+    );
+    assert!(params.reheat_patience > 0);
+    assert!(
+        MODES.contains(&params.mode.as_str()),
+        "Unknown mode '{}', expected one of {:?}",
+        params.mode,
+        MODES
+    );
+    assert!(params.pt_replicas > 0);
+    assert!(
+        params.pt_temps.iter().all(|&temp| temp > F::zero()),
+        "Parallel tempering temperatures must be positive"
+    );
+    assert!(params.pt_swap_interval > 0);
+    assert!(params.pa_size > 0);
+    assert!(params.pa_interval > 0);
+    assert!(
+        params.wl_e_max > params.wl_e_min,
+        "wl_e_max must be larger than wl_e_min"
+    );
+    assert!(params.wl_bins > 0);
+    assert!(params.wl_flatness > F::zero() && params.wl_flatness < F::one());
+    assert!(params.wl_check_interval > 0);
+    assert!(
+        WL_SCHEDULES.contains(&params.wl_schedule.as_str()),
+        "Unknown Wang-Landau schedule '{}', expected one of {:?}",
+        params.wl_schedule,
+        WL_SCHEDULES
+    );
+    assert!(params.wl_ln_f_final > F::zero());
+    assert!(
+        MINIMIZER_NAMES.contains(&params.bh_minimizer.as_str()),
+        "Unknown local minimizer '{}', expected one of {:?}",
+        params.bh_minimizer,
+        MINIMIZER_NAMES
+    );
+    assert!(params.bh_local_steps > 0);
+    assert!(params.bh_tolerance > F::zero());
+    assert!(params.bh_step > F::zero());
+    assert!(params.bh_basin_radius > F::zero());
+    assert!(params.ens_runs > 0);
+    assert!(params.ens_success >= F::zero());
+    assert!(
+        params.sweep.is_empty() || params.mode == "mc",
+        "Parameter sweeps are only available in mode mc"
+    );
+    for (k, axis) in params.sweep.iter().enumerate() {
+        assert!(
+            params.sweep[..k].iter().all(|a| a.key != axis.key),
+            "{} is swept twice",
+            axis.key
+        );
+        // negative final temperatures mean constant temperature
+        assert!(
+            axis.key == "final_temp" || axis.values.iter().all(|&v| v > 0.0),
+            "Swept {} values must be positive",
+            axis.key
+        );
+        assert!(
+            axis.key != "n_step" || axis.values.iter().all(|&v| v.fract() == 0.0),
+            "Swept n_step values must be integers"
+        );
+    }
+    assert!(params.reheat_fraction > F::zero());
+    assert!(
+        TRAJECTORY_MODES.contains(&params.trajectory.as_str()),
+        "Unknown trajectory mode '{}', expected one of {:?}",
+        params.trajectory,
+        TRAJECTORY_MODES
+    );
     assert!(!params.x_ini.is_empty());
+    assert!(
+        FUNCTION_NAMES.contains(&params.function.as_str()),
+        "Unknown function '{}', expected one of {:?}",
+        params.function,
+        FUNCTION_NAMES
+    );
+    assert!(
+        params.shift.is_empty() || params.shift.len() == params.x_ini.len(),
+        "shift and x_ini must have the same dimension"
+    );
+    assert!(
+        params.rotation.is_empty() || params.rotation.len() == params.x_ini.len(),
+        "rotation matrix and x_ini must have the same dimension"
+    );
+
+    assert!(
+        params.lower.len() == params.upper.len()
+            && (params.lower.is_empty() || params.lower.len() == params.x_ini.len()),
+        "lower and upper must both be set, with one value or one per dimension"
+    );
+    assert!(
+        params
+            .lower
+            .iter()
+            .zip(&params.upper)
+            .all(|(lo, up)| lo < up),
+        "lower must be below upper in every dimension"
+    );
+    assert!(
+        params
+            .x_ini
+            .iter()
+            .zip(params.lower.iter().zip(&params.upper))
+            .all(|(x, (lo, up))| x >= lo && x <= up),
+        "x_ini must lie within the bounds"
+    );
+    assert!(
+        BOUNDARY_POLICIES.contains(&params.boundary.as_str()),
+        "Unknown boundary policy '{}', expected one of {:?}",
+        params.boundary,
+        BOUNDARY_POLICIES
+    );
+    assert!(
+        params.lower.is_empty() || params.sampler == "metropolis" || params.boundary == "reject",
+        "The {} sampler needs boundary reject",
+        params.sampler
+    );
+
+    // every point of a sweep is a plain run and must be valid on its own,
+    // e.g. burn_in <= n_step for every swept n_step
+    if !params.sweep.is_empty() {
+        for point in sweep_points(&params) {
+            validate_amc_params(point);
+        }
+    }
 
     params.foutname = "out/".to_owned() + &params.foutname;
 
     // if no/negative final temp was set, assign ini_temp -> no sim. annealing
-    if params.final_temp.is_sign_negative() {
+    // (per point of a sweep, see sweep::sweep_points)
+    if params.final_temp.is_sign_negative() && params.sweep.is_empty() {
         params.final_temp = params.ini_temp;
     }
     return params;
 }
 
+/// Reads a matrix with one comma-separated row per line.
+fn read_matrix<F: McFloat>(filename: String) -> Vec<Vec<F>> {
+    let file_contents = read_to_string(filename.clone())
+        .unwrap_or_else(|_| panic!("Err: Cannot read matrix file '{}'", filename));
+
+    return file_contents
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            l.split(',')
+                .map(|s| parse_float(s.trim(), "matrix entry"))
+                .collect::<Vec<F>>()
+        })
+        .collect();
+}
+
+/// Reads a cooling schedule with one comma-separated `step,temperature` pair per line.
+fn read_schedule_table<F: McFloat>(filename: String) -> Vec<(u64, F)> {
+    let file_contents = read_to_string(filename.clone())
+        .unwrap_or_else(|_| panic!("Err: Cannot read schedule file '{}'", filename));
+
+    return file_contents
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            let (step, temp) = l.split_once(',').expect("Bad line (schedule table)");
+            let step = step
+                .trim()
+                .parse::<u64>()
+                .expect("Bad line (schedule table)");
+            return (step, parse_float(temp.trim(), "schedule table"));
+        })
+        .collect();
+}
+
 // -------------------- write ackley mc results as csv to file --------------------
 
 pub fn create_out_dir() {
@@ -65,43 +551,198 @@ pub fn create_out_dir() {
     fs::create_dir_all("out").expect("Cannot create 'out' directory");
 }
 
-fn to_csv_line(step: usize, x: Vec<f32>, e: f32) -> String {
-    let xstr: String = x.iter().map(|&xi| xi.to_string() + ",").collect();
-    return format!("{},{}{}", step, xstr.as_str(), e);
+/// Writes the accepted states of an in-memory run as csv, in the same format
+/// as streaming the run through a `CsvSink`.
+#[allow(dead_code)]
+pub fn write_res_to_file<F: McFloat>(res: AckleyMcResult<F>, filename: String) {
+    let mut sink = CsvSink::new(create_out_file(&filename));
+
+    let mut accepted = res.accepted_steps.iter().peekable();
+    for (i, (x, &e)) in res.x_vals.iter().zip(&res.e_vals).enumerate() {
+        let step: u64 = res.steps[i];
+        // Trial move k leads to the state recorded at step k + 1, so the
+        // state changed if a move before `step` was accepted since the last record.
+        let mut moved: bool = i == 0;
+        while accepted.next_if(|&&k| k < step).is_some() {
+            moved = true;
+        }
+        sink.record(step, x, e, moved);
+    }
+    sink.finish();
+    println!("CSV data has been saved to {}", &filename);
 }
 
-#[allow(dead_code)]
-pub fn write_res_to_file(res: AckleyMcResult, filename: String) {
-    let mut out = BufWriter::new(
-        std::fs::File::create(filename.clone())
-            .expect(format!("Could not create csv file '{}'", &filename).as_str()),
-    );
+/// Writes the temperature of each recorded state as csv lines (step,temperature).
+pub fn write_temps_to_file<F: McFloat>(res: &AckleyMcResult<F>, filename: String) {
+    let mut out = create_out_file(&filename);
+    for (step, temp) in res.steps.iter().zip(&res.t_vals) {
+        writeln!(out, "{},{}", step, temp).expect("Could not write temperature csv line");
+    }
+    out.flush().expect("Could not flush temperature csv");
+    println!("Temperatures have been saved to {}", &filename);
+}
 
-    let mut step: usize = 0;
-    for i in 0..res.accepted_steps.len() {
-        step = res.accepted_steps[i] as usize;
+/// Writes the population annealing statistics, one csv line per temperature.
+pub fn write_population_to_file<F: McFloat>(res: &PopulationResult<F>, filename: String) {
+    let mut out = create_out_file(&filename);
+    writeln!(
+        out,
+        "step,temperature,population,n_families,effective_families,e_mean,free_energy"
+    )
+    .expect("Could not write population csv header");
+    for stage in &res.stages {
         writeln!(
             out,
-            "{}",
-            to_csv_line(step, res.x_vals[step].clone(), res.e_vals[step])
+            "{},{},{},{},{},{},{}",
+            stage.step,
+            stage.temperature,
+            stage.population,
+            stage.n_families,
+            stage.effective_families,
+            stage.e_mean,
+            stage.free_energy
         )
-        .ok();
+        .expect("Could not write population csv line");
     }
+    out.flush().expect("Could not flush population csv");
+    println!("Population statistics have been saved to {}", &filename);
+}
 
-    // Add line for final step taken if it was not accepted.
-    if step < res.n_steps as usize {
+/// Writes ln g(E) of the visited Wang-Landau bins as csv lines (energy,ln_g),
+/// energy being the bin centre.
+pub fn write_dos_to_file<F: McFloat>(res: &WangLandauResult<F>, filename: String) {
+    let mut out = create_out_file(&filename);
+    writeln!(out, "energy,ln_g").expect("Could not write density of states csv header");
+    for (k, ln_g) in res.ln_g.iter().enumerate() {
+        if res.visited[k] {
+            writeln!(out, "{},{}", res.bin_energy(k), ln_g)
+                .expect("Could not write density of states csv line");
+        }
+    }
+    out.flush().expect("Could not flush density of states csv");
+    println!("Density of states has been saved to {}", &filename);
+}
+
+/// Writes the distinct basins (id,energy,n_visits,x...) and the hops
+/// (hop,from,to,temperature,accepted, `to` empty for hops that left the
+/// bounds) of a basin hopping run as two csv files, i.e. the nodes and edges
+/// of the basin graph.
+pub fn write_basins_to_file<F: McFloat>(
+    res: &BasinHoppingResult<F>,
+    basins_filename: String,
+    hops_filename: String,
+) {
+    let mut out = create_out_file(&basins_filename);
+    for (id, basin) in res.basins.iter().enumerate() {
+        write!(out, "{},{},{}", id, basin.energy, basin.n_visits)
+            .expect("Could not write basin csv line");
+        for xi in &basin.x {
+            write!(out, ",{}", xi).expect("Could not write basin csv line");
+        }
+        writeln!(out).expect("Could not write basin csv line");
+    }
+    out.flush().expect("Could not flush basin csv");
+    println!("Basins have been saved to {}", &basins_filename);
+
+    let mut out = create_out_file(&hops_filename);
+    for hop in &res.hops {
         writeln!(
             out,
-            "{}",
-            to_csv_line(
-                res.n_steps as usize,
-                res.x_vals[step].clone(),
-                res.e_vals[step]
-            )
+            "{},{},{},{},{}",
+            hop.hop,
+            hop.from,
+            hop.to.map_or(String::new(), |to| to.to_string()),
+            hop.temperature,
+            hop.accepted
         )
-        .ok();
+        .expect("Could not write hop csv line");
     }
-    out.flush()
-        .expect(format!("Could not write to csv file '{}'", &filename).as_str());
-    println!("CSV data has been saved to {}", &filename);
+    out.flush().expect("Could not flush hop csv");
+    println!("Hops have been saved to {}", &hops_filename);
+}
+
+/// Writes one csv line per ensemble run (run,seed,n_accepted,e_best,success,
+/// x_best...) and the aggregated statistics of e_best as a one-line csv.
+pub fn write_ensemble_to_file<F: McFloat>(
+    res: &EnsembleResult<F>,
+    runs_filename: String,
+    summary_filename: String,
+) {
+    let mut out = create_out_file(&runs_filename);
+    for (k, run) in res.runs.iter().enumerate() {
+        write!(
+            out,
+            "{},{},{},{},{}",
+            k,
+            res.seeds[k],
+            run.n_accepted,
+            run.e_best,
+            res.is_success(k)
+        )
+        .expect("Could not write ensemble csv line");
+        for xi in &run.x_best {
+            write!(out, ",{}", xi).expect("Could not write ensemble csv line");
+        }
+        writeln!(out).expect("Could not write ensemble csv line");
+    }
+    out.flush().expect("Could not flush ensemble csv");
+    println!("Ensemble runs have been saved to {}", &runs_filename);
+
+    let mut out = create_out_file(&summary_filename);
+    writeln!(
+        out,
+        "n_runs,success_rate,e_best_min,e_best_q10,e_best_q25,e_best_median,e_best_q75,e_best_q90,e_best_max,best_run"
+    )
+    .expect("Could not write ensemble summary header");
+    writeln!(
+        out,
+        "{},{},{},{},{},{},{},{},{},{}",
+        res.runs.len(),
+        res.success_rate(),
+        res.e_best_quantile(0.0),
+        res.e_best_quantile(0.1),
+        res.e_best_quantile(0.25),
+        res.e_best_quantile(0.5),
+        res.e_best_quantile(0.75),
+        res.e_best_quantile(0.9),
+        res.e_best_quantile(1.0),
+        res.best_run()
+    )
+    .expect("Could not write ensemble summary line");
+    out.flush().expect("Could not flush ensemble summary");
+    println!("Ensemble summary has been saved to {}", &summary_filename);
+}
+
+/// Writes the sweep table: one csv line per point with the values of the
+/// swept parameters, the acceptance rate and the final, mean and best energy.
+pub fn write_sweep_to_file<F: McFloat>(res: &SweepResult<F>, filename: String) {
+    let mut out = create_out_file(&filename);
+    for axis in &res.axes {
+        write!(out, "{},", axis.key).expect("Could not write sweep csv header");
+    }
+    writeln!(out, "n_accepted,acceptance_rate,e_final,e_mean,e_best")
+        .expect("Could not write sweep csv header");
+    for point in &res.points {
+        for value in &point.values {
+            write!(out, "{},", value).expect("Could not write sweep csv line");
+        }
+        writeln!(
+            out,
+            "{},{},{},{},{}",
+            point.n_accepted,
+            point.n_accepted as f64 / point.n_steps as f64,
+            point.e_final,
+            point.e_mean,
+            point.e_best
+        )
+        .expect("Could not write sweep csv line");
+    }
+    out.flush().expect("Could not flush sweep csv");
+    println!("Sweep table has been saved to {}", &filename);
+}
+
+pub fn create_out_file(filename: &str) -> BufWriter<File> {
+    return BufWriter::new(
+        File::create(filename).unwrap_or_else(|_| panic!("Could not create file '{}'", filename)),
+    );
 }
@@ -0,0 +1,65 @@
+use float_cmp::ApproxEq;
+use num_traits::{Float, FloatConst, FromPrimitive, NumAssign};
+use rand::distributions::uniform::SampleUniform;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
+use std::str::FromStr;
+
+// -------------------- float abstraction --------------------
+
+/// Floating point type the engine, the objectives and the results can be
+/// instantiated with, i.e. `f32` or `f64`.
+pub trait McFloat:
+    Float
+    + FloatConst
+    + FromPrimitive
+    + NumAssign
+    + Sum
+    + Default
+    + Debug
+    + Display
+    + FromStr
+    + SampleUniform
+    + Send
+    + Sync
+    + 'static
+{
+    /// Converts a literal/constant; every f64 is representable (up to rounding) in both types.
+    #[inline]
+    fn lit(v: f64) -> Self {
+        return Self::from_f64(v).unwrap();
+    }
+
+    /// Equality up to 2 units in the last place.
+    fn approx_eq_ulps(self, other: Self) -> bool;
+
+    /// IEEE 754 total order, so sorting never panics on NaN.
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+impl McFloat for f32 {
+    fn approx_eq_ulps(self, other: Self) -> bool {
+        return self.approx_eq(other, (0.0, 2));
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        return f32::total_cmp(self, other);
+    }
+}
+
+impl McFloat for f64 {
+    fn approx_eq_ulps(self, other: Self) -> bool {
+        return self.approx_eq(other, (0.0, 2));
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        return f64::total_cmp(self, other);
+    }
+}
+
+/// Lossy conversion to f64, e.g. for plotting.
+#[inline]
+pub fn to_f64<F: McFloat>(v: F) -> f64 {
+    return v.to_f64().unwrap();
+}
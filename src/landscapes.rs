@@ -0,0 +1,616 @@
+use crate::ackley_mc::{Ackley, AckleyMcParams};
+use crate::float::McFloat;
+use crate::objective::{DeltaObjective, Objective};
+use crate::transform::{Rotated, Shifted};
+
+// -------------------- lookup by name --------------------
+
+/// Names accepted by `objective_from_name` (and the `function` parameter file key).
+pub const FUNCTION_NAMES: [&str; 10] = [
+    "ackley",
+    "rastrigin",
+    "rosenbrock",
+    "schwefel",
+    "griewank",
+    "levy",
+    "styblinski_tang",
+    "michalewicz",
+    "sphere",
+    "zakharov",
+];
+
+pub fn objective_from_name<F: McFloat>(name: &str) -> Option<Box<dyn Objective<F>>> {
+    let objective: Box<dyn Objective<F>> = match name {
+        "ackley" => Box::new(Ackley::default()),
+        "rastrigin" => Box::new(Rastrigin),
+        "rosenbrock" => Box::new(Rosenbrock),
+        "schwefel" => Box::new(Schwefel),
+        "griewank" => Box::new(Griewank),
+        "levy" => Box::new(Levy),
+        "styblinski_tang" => Box::new(StyblinskiTang),
+        "michalewicz" => Box::new(Michalewicz::default()),
+        "sphere" => Box::new(Sphere),
+        "zakharov" => Box::new(Zakharov),
+        _ => return None,
+    };
+    return Some(objective);
+}
+
+/// Like `objective_from_name`, but also applies the function constants and the
+/// shift/rotation set in `params`, i.e. evaluates f(M (x - shift)).
+pub fn objective_from_params<F: McFloat>(
+    params: &AckleyMcParams<F>,
+) -> Option<Box<dyn Objective<F>>> {
+    let mut objective: Box<dyn Objective<F>> = if params.function == "ackley" {
+        Box::new(Ackley {
+            a: params.ack_a,
+            b: params.ack_b,
+            c: params.ack_c,
+        })
+    } else {
+        objective_from_name(&params.function)?
+    };
+
+    if !params.rotation.is_empty() {
+        objective = Box::new(Rotated::new(objective, params.rotation.clone()));
+    }
+    if !params.shift.is_empty() {
+        objective = Box::new(Shifted::new(objective, params.shift.clone()));
+    }
+    return Some(objective);
+}
+
+// -------------------- benchmark landscapes --------------------
+
+/// Rastrigin: f(x) = 10n + sum(x_i^2 - 10 cos(2 pi x_i)).
+/// Domain [-5.12, 5.12]^n, global minimum f(0, ..., 0) = 0.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rastrigin;
+
+impl<F: McFloat> Objective<F> for Rastrigin {
+    fn evaluate(&self, x: &[F]) -> F {
+        let ten = F::lit(10.0);
+        let sum: F = x
+            .iter()
+            .map(|&xi| xi * xi - ten * (F::lit(2.0) * F::PI() * xi).cos())
+            .sum();
+        return ten * F::from_usize(x.len()).unwrap() + sum;
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return Some((F::lit(-5.12), F::lit(5.12)));
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        return Some((vec![F::zero(); dim], F::zero()));
+    }
+
+    fn name(&self) -> String {
+        return "Rastrigin".to_string();
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        let two_pi = F::lit(2.0) * F::PI();
+        for (g, &xi) in grad.iter_mut().zip(x) {
+            *g = F::lit(2.0) * xi + F::lit(10.0) * two_pi * (two_pi * xi).sin();
+        }
+        return true;
+    }
+
+    fn delta(&self) -> Option<&dyn DeltaObjective<F>> {
+        return Some(self);
+    }
+}
+
+impl<F: McFloat> DeltaObjective<F> for Rastrigin {
+    fn n_sums(&self) -> usize {
+        return 1;
+    }
+
+    fn accumulate(&self, sums: &mut [F], _i: usize, xi: F, weight: F) {
+        sums[0] += weight * (xi * xi - F::lit(10.0) * (F::lit(2.0) * F::PI() * xi).cos());
+    }
+
+    fn energy_from_sums(&self, sums: &[F], dim: usize) -> F {
+        return F::lit(10.0) * F::from_usize(dim).unwrap() + sums[0];
+    }
+}
+
+/// Rosenbrock: f(x) = sum(100 (x_{i+1} - x_i^2)^2 + (x_i - 1)^2).
+/// Domain [-5, 10]^n, global minimum f(1, ..., 1) = 0.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rosenbrock;
+
+impl<F: McFloat> Objective<F> for Rosenbrock {
+    fn evaluate(&self, x: &[F]) -> F {
+        let mut y: F = F::zero();
+        for w in x.windows(2) {
+            y += F::lit(100.0) * (w[1] - w[0] * w[0]).powi(2) + (w[0] - F::one()).powi(2);
+        }
+        return y;
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return Some((F::lit(-5.0), F::lit(10.0)));
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        return Some((vec![F::one(); dim], F::zero()));
+    }
+
+    fn name(&self) -> String {
+        return "Rosenbrock".to_string();
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        grad.fill(F::zero());
+        for i in 0..x.len().saturating_sub(1) {
+            let inner: F = x[i + 1] - x[i] * x[i];
+            grad[i] += -F::lit(400.0) * x[i] * inner + F::lit(2.0) * (x[i] - F::one());
+            grad[i + 1] += F::lit(200.0) * inner;
+        }
+        return true;
+    }
+}
+
+/// Schwefel: f(x) = 418.9829 n - sum(x_i sin(sqrt(|x_i|))).
+/// Domain [-500, 500]^n, global minimum f(420.9687, ..., 420.9687) = 0.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Schwefel;
+
+impl<F: McFloat> Objective<F> for Schwefel {
+    fn evaluate(&self, x: &[F]) -> F {
+        let sum: F = x.iter().map(|&xi| xi * xi.abs().sqrt().sin()).sum();
+        return F::lit(418.9829) * F::from_usize(x.len()).unwrap() - sum;
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return Some((F::lit(-500.0), F::lit(500.0)));
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        return Some((vec![F::lit(420.9687); dim], F::zero()));
+    }
+
+    fn name(&self) -> String {
+        return "Schwefel".to_string();
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        // d/dx x sin(sqrt|x|) = sin(sqrt|x|) + sqrt|x| cos(sqrt|x|) / 2
+        for (g, &xi) in grad.iter_mut().zip(x) {
+            let root: F = xi.abs().sqrt();
+            *g = -(root.sin() + F::lit(0.5) * root * root.cos());
+        }
+        return true;
+    }
+
+    fn delta(&self) -> Option<&dyn DeltaObjective<F>> {
+        return Some(self);
+    }
+}
+
+impl<F: McFloat> DeltaObjective<F> for Schwefel {
+    fn n_sums(&self) -> usize {
+        return 1;
+    }
+
+    fn accumulate(&self, sums: &mut [F], _i: usize, xi: F, weight: F) {
+        sums[0] += weight * xi * xi.abs().sqrt().sin();
+    }
+
+    fn energy_from_sums(&self, sums: &[F], dim: usize) -> F {
+        return F::lit(418.9829) * F::from_usize(dim).unwrap() - sums[0];
+    }
+}
+
+/// Griewank: f(x) = 1 + sum(x_i^2) / 4000 - prod(cos(x_i / sqrt(i))), i = 1..n.
+/// Domain [-600, 600]^n, global minimum f(0, ..., 0) = 0.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Griewank;
+
+impl<F: McFloat> Objective<F> for Griewank {
+    fn evaluate(&self, x: &[F]) -> F {
+        let mut square_sum: F = F::zero();
+        let mut cosine_prod: F = F::one();
+        for (i, &xi) in x.iter().enumerate() {
+            square_sum += xi * xi;
+            cosine_prod *= (xi / F::from_usize(i + 1).unwrap().sqrt()).cos();
+        }
+        return F::one() + square_sum / F::lit(4000.0) - cosine_prod;
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return Some((F::lit(-600.0), F::lit(600.0)));
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        return Some((vec![F::zero(); dim], F::zero()));
+    }
+
+    fn name(&self) -> String {
+        return "Griewank".to_string();
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        // product of the cosines of all other coordinates: prefix, then suffix
+        let mut prefix: F = F::one();
+        for (i, (g, &xi)) in grad.iter_mut().zip(x).enumerate() {
+            *g = prefix;
+            prefix *= (xi / F::from_usize(i + 1).unwrap().sqrt()).cos();
+        }
+        let mut suffix: F = F::one();
+        for (i, (g, &xi)) in grad.iter_mut().zip(x).enumerate().rev() {
+            let root_i: F = F::from_usize(i + 1).unwrap().sqrt();
+            let others: F = *g * suffix;
+            *g = xi / F::lit(2000.0) + (xi / root_i).sin() / root_i * others;
+            suffix *= (xi / root_i).cos();
+        }
+        return true;
+    }
+}
+
+/// Levy: with w_i = 1 + (x_i - 1) / 4,
+/// f(x) = sin^2(pi w_1) + sum_{i<n}((w_i - 1)^2 (1 + 10 sin^2(pi w_i + 1)))
+///        + (w_n - 1)^2 (1 + sin^2(2 pi w_n)).
+/// Domain [-10, 10]^n, global minimum f(1, ..., 1) = 0.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Levy;
+
+impl<F: McFloat> Objective<F> for Levy {
+    fn evaluate(&self, x: &[F]) -> F {
+        let one = F::one();
+        let pi = F::PI();
+        let w = |xi: F| one + (xi - one) / F::lit(4.0);
+        let n = x.len();
+
+        let mut y: F = (pi * w(x[0])).sin().powi(2);
+        for &xi in &x[..n - 1] {
+            let wi = w(xi);
+            y += (wi - one).powi(2) * (one + F::lit(10.0) * (pi * wi + one).sin().powi(2));
+        }
+        let wn = w(x[n - 1]);
+        y += (wn - one).powi(2) * (one + (F::lit(2.0) * pi * wn).sin().powi(2));
+        return y;
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return Some((F::lit(-10.0), F::lit(10.0)));
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        return Some((vec![F::one(); dim], F::zero()));
+    }
+
+    fn name(&self) -> String {
+        return "Levy".to_string();
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        let one = F::one();
+        let two = F::lit(2.0);
+        let pi = F::PI();
+        let w = |xi: F| one + (xi - one) / F::lit(4.0);
+        let n = x.len();
+
+        // derivatives with respect to w_i, times dw_i/dx_i = 1/4
+        for (i, (g, &xi)) in grad.iter_mut().zip(x).enumerate() {
+            let wi = w(xi);
+            let mut dw: F = F::zero();
+            if i == 0 {
+                dw += pi * (two * pi * wi).sin();
+            }
+            if i < n - 1 {
+                let s = (pi * wi + one).sin();
+                dw += two * (wi - one) * (one + F::lit(10.0) * s * s)
+                    + (wi - one).powi(2) * F::lit(10.0) * pi * (two * (pi * wi + one)).sin();
+            } else {
+                let s = (two * pi * wi).sin();
+                dw += two * (wi - one) * (one + s * s)
+                    + (wi - one).powi(2) * two * pi * (F::lit(4.0) * pi * wi).sin();
+            }
+            *g = dw / F::lit(4.0);
+        }
+        return true;
+    }
+}
+
+/// Styblinski-Tang: f(x) = 1/2 sum(x_i^4 - 16 x_i^2 + 5 x_i).
+/// Domain [-5, 5]^n, global minimum f(-2.903534, ..., -2.903534) = -39.16599 n.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StyblinskiTang;
+
+impl<F: McFloat> Objective<F> for StyblinskiTang {
+    fn evaluate(&self, x: &[F]) -> F {
+        let sum: F = x
+            .iter()
+            .map(|&xi| xi.powi(4) - F::lit(16.0) * xi * xi + F::lit(5.0) * xi)
+            .sum();
+        return F::lit(0.5) * sum;
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return Some((F::lit(-5.0), F::lit(5.0)));
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        let e_min = F::lit(-39.16599) * F::from_usize(dim).unwrap();
+        return Some((vec![F::lit(-2.903534); dim], e_min));
+    }
+
+    fn name(&self) -> String {
+        return "Styblinski-Tang".to_string();
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        for (g, &xi) in grad.iter_mut().zip(x) {
+            *g = F::lit(2.0) * xi.powi(3) - F::lit(16.0) * xi + F::lit(2.5);
+        }
+        return true;
+    }
+
+    fn delta(&self) -> Option<&dyn DeltaObjective<F>> {
+        return Some(self);
+    }
+}
+
+impl<F: McFloat> DeltaObjective<F> for StyblinskiTang {
+    fn n_sums(&self) -> usize {
+        return 1;
+    }
+
+    fn accumulate(&self, sums: &mut [F], _i: usize, xi: F, weight: F) {
+        sums[0] += weight * (xi.powi(4) - F::lit(16.0) * xi * xi + F::lit(5.0) * xi);
+    }
+
+    fn energy_from_sums(&self, sums: &[F], _dim: usize) -> F {
+        return F::lit(0.5) * sums[0];
+    }
+}
+
+/// Michalewicz: f(x) = -sum(sin(x_i) sin^(2m)(i x_i^2 / pi)), i = 1..n, usually m = 10.
+/// Domain [0, pi]^n. The global minimum is only tabulated for a few dimensions,
+/// e.g. f(2.20, 1.57) = -1.8013 in 2D.
+#[derive(Clone, Copy, Debug)]
+pub struct Michalewicz {
+    pub m: i32,
+}
+
+impl Default for Michalewicz {
+    fn default() -> Self {
+        return Michalewicz { m: 10 };
+    }
+}
+
+impl<F: McFloat> Objective<F> for Michalewicz {
+    fn evaluate(&self, x: &[F]) -> F {
+        let mut y: F = F::zero();
+        for (i, &xi) in x.iter().enumerate() {
+            let ripple = (F::from_usize(i + 1).unwrap() * xi * xi / F::PI()).sin();
+            y -= xi.sin() * ripple.powi(2 * self.m);
+        }
+        return y;
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return Some((F::zero(), F::PI()));
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        if dim == 2 && self.m == 10 {
+            return Some((vec![F::lit(2.20), F::lit(1.57)], F::lit(-1.8013)));
+        }
+        return None;
+    }
+
+    fn name(&self) -> String {
+        return "Michalewicz".to_string();
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        let two_m = 2 * self.m;
+        for (i, (g, &xi)) in grad.iter_mut().zip(x).enumerate() {
+            let k: F = F::from_usize(i + 1).unwrap();
+            let arg: F = k * xi * xi / F::PI();
+            let ripple: F = arg.sin();
+            let d_ripple: F = arg.cos() * F::lit(2.0) * k * xi / F::PI();
+            *g = -(xi.cos() * ripple.powi(two_m)
+                + xi.sin() * F::from_i32(two_m).unwrap() * ripple.powi(two_m - 1) * d_ripple);
+        }
+        return true;
+    }
+
+    fn delta(&self) -> Option<&dyn DeltaObjective<F>> {
+        return Some(self);
+    }
+}
+
+impl<F: McFloat> DeltaObjective<F> for Michalewicz {
+    fn n_sums(&self) -> usize {
+        return 1;
+    }
+
+    fn accumulate(&self, sums: &mut [F], i: usize, xi: F, weight: F) {
+        let ripple = (F::from_usize(i + 1).unwrap() * xi * xi / F::PI()).sin();
+        sums[0] -= weight * xi.sin() * ripple.powi(2 * self.m);
+    }
+
+    fn energy_from_sums(&self, sums: &[F], _dim: usize) -> F {
+        return sums[0];
+    }
+}
+
+/// Sphere: f(x) = sum(x_i^2).
+/// Domain [-5.12, 5.12]^n, global minimum f(0, ..., 0) = 0.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sphere;
+
+impl<F: McFloat> Objective<F> for Sphere {
+    fn evaluate(&self, x: &[F]) -> F {
+        return x.iter().map(|&xi| xi * xi).sum();
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return Some((F::lit(-5.12), F::lit(5.12)));
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        return Some((vec![F::zero(); dim], F::zero()));
+    }
+
+    fn name(&self) -> String {
+        return "Sphere".to_string();
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        for (g, &xi) in grad.iter_mut().zip(x) {
+            *g = F::lit(2.0) * xi;
+        }
+        return true;
+    }
+
+    fn delta(&self) -> Option<&dyn DeltaObjective<F>> {
+        return Some(self);
+    }
+}
+
+impl<F: McFloat> DeltaObjective<F> for Sphere {
+    fn n_sums(&self) -> usize {
+        return 1;
+    }
+
+    fn accumulate(&self, sums: &mut [F], _i: usize, xi: F, weight: F) {
+        sums[0] += weight * xi * xi;
+    }
+
+    fn energy_from_sums(&self, sums: &[F], _dim: usize) -> F {
+        return sums[0];
+    }
+}
+
+/// Zakharov: with s = sum(0.5 i x_i), i = 1..n, f(x) = sum(x_i^2) + s^2 + s^4.
+/// Domain [-5, 10]^n, global minimum f(0, ..., 0) = 0.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Zakharov;
+
+impl<F: McFloat> Objective<F> for Zakharov {
+    fn evaluate(&self, x: &[F]) -> F {
+        let mut square_sum: F = F::zero();
+        let mut weighted_sum: F = F::zero();
+        for (i, &xi) in x.iter().enumerate() {
+            square_sum += xi * xi;
+            weighted_sum += F::lit(0.5) * F::from_usize(i + 1).unwrap() * xi;
+        }
+        return square_sum + weighted_sum.powi(2) + weighted_sum.powi(4);
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return Some((F::lit(-5.0), F::lit(10.0)));
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        return Some((vec![F::zero(); dim], F::zero()));
+    }
+
+    fn name(&self) -> String {
+        return "Zakharov".to_string();
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        let weighted_sum: F = x
+            .iter()
+            .enumerate()
+            .map(|(i, &xi)| F::lit(0.5) * F::from_usize(i + 1).unwrap() * xi)
+            .sum();
+        let outer: F = F::lit(2.0) * weighted_sum + F::lit(4.0) * weighted_sum.powi(3);
+        for (i, (g, &xi)) in grad.iter_mut().zip(x).enumerate() {
+            *g = F::lit(2.0) * xi + outer * F::lit(0.5) * F::from_usize(i + 1).unwrap();
+        }
+        return true;
+    }
+
+    fn delta(&self) -> Option<&dyn DeltaObjective<F>> {
+        return Some(self);
+    }
+}
+
+impl<F: McFloat> DeltaObjective<F> for Zakharov {
+    fn n_sums(&self) -> usize {
+        return 2;
+    }
+
+    fn accumulate(&self, sums: &mut [F], i: usize, xi: F, weight: F) {
+        sums[0] += weight * xi * xi;
+        sums[1] += weight * F::lit(0.5) * F::from_usize(i + 1).unwrap() * xi;
+    }
+
+    fn energy_from_sums(&self, sums: &[F], _dim: usize) -> F {
+        return sums[0] + sums[1].powi(2) + sums[1].powi(4);
+    }
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_landscapes_delta_matches_evaluate() {
+    let x: Vec<f64> = vec![0.3, -1.2, 2.5, 0.9];
+    for name in FUNCTION_NAMES {
+        let objective = objective_from_name::<f64>(name).unwrap();
+        if let Some(delta) = objective.delta() {
+            let mut sums = delta.sums(&x);
+            let e = delta.energy_from_sums(&sums, x.len());
+            assert!((e - objective.evaluate(&x)).abs() <= 1e-9, "{name}");
+
+            let mut x_trial = x.clone();
+            x_trial[2] = -0.7;
+            delta.update_sums(&mut sums, 2, x[2], x_trial[2]);
+            let e_trial = delta.energy_from_sums(&sums, x.len());
+            assert!(
+                (e_trial - objective.evaluate(&x_trial)).abs() <= 1e-9,
+                "{name}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_landscapes_at_global_minimum() {
+    for name in FUNCTION_NAMES {
+        let objective = objective_from_name::<f32>(name).unwrap();
+        let (x_min, e_min) = objective.global_minimum(2).unwrap();
+        let e = objective.evaluate(&x_min);
+        assert!((e - e_min).abs() <= 0.001, "{name}: {e} != {e_min}");
+
+        let (lower, upper) = objective.domain().unwrap();
+        assert!(x_min.iter().all(|&xi| lower <= xi && xi <= upper));
+    }
+}
+
+#[test]
+fn test_landscapes_unknown_name() {
+    assert!(objective_from_name::<f64>("himmelblau").is_none());
+}
+
+#[test]
+fn test_gradients_match_finite_differences() {
+    let x: Vec<f64> = vec![0.7, -1.3, 2.1, 0.4];
+    let h: f64 = 1e-6;
+    for name in FUNCTION_NAMES {
+        let objective = objective_from_name::<f64>(name).unwrap();
+        let mut grad: Vec<f64> = vec![0.0; x.len()];
+        assert!(objective.gradient(&x, &mut grad), "{name}");
+        for i in 0..x.len() {
+            let mut xp = x.clone();
+            let mut xm = x.clone();
+            xp[i] += h;
+            xm[i] -= h;
+            let numeric = (objective.evaluate(&xp) - objective.evaluate(&xm)) / (2.0 * h);
+            let tolerance = 1e-5 * (1.0 + numeric.abs());
+            assert!(
+                (grad[i] - numeric).abs() < tolerance,
+                "{name} {i}: {} {numeric}",
+                grad[i]
+            );
+        }
+    }
+}
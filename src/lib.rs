@@ -1 +1,20 @@
+#![allow(clippy::needless_return)]
+
+pub mod acceptance;
 pub mod ackley_mc;
+pub mod basin_hopping;
+pub mod bounds;
+pub mod chain;
+pub mod ensemble;
+pub mod float;
+pub mod gradient;
+pub mod landscapes;
+pub mod objective;
+pub mod population;
+pub mod proposal;
+pub mod schedule;
+pub mod sweep;
+pub mod tempering;
+pub mod trajectory;
+pub mod transform;
+pub mod wang_landau;
@@ -1,12 +1,24 @@
+#![allow(clippy::needless_return)]
+
 use clap::Parser;
 use std::error::Error;
 
-use crate::ackley_mc::run_ackley_mc;
-use crate::ackley_mc::AckleyMcParams;
-use crate::ackley_mc::AckleyMcResult;
+use ackley_mc::ackley_mc::run_mc;
+use ackley_mc::ackley_mc::run_mc_with_sink;
+use ackley_mc::ackley_mc::AckleyMcParams;
+use ackley_mc::ackley_mc::AckleyMcResult;
+use ackley_mc::basin_hopping::{run_basin_hopping, BasinHoppingResult};
+use ackley_mc::ensemble::{run_ensemble, thread_count, EnsembleResult};
+use ackley_mc::float::{to_f64, McFloat};
+use ackley_mc::landscapes::objective_from_params;
+use ackley_mc::objective::Objective;
+use ackley_mc::population::{run_population_annealing, PopulationResult};
+use ackley_mc::sweep::{run_sweep, SweepResult};
+use ackley_mc::tempering::{run_parallel_tempering, TemperingResult};
+use ackley_mc::trajectory::{BinarySink, CsvSink, SummarySink};
+use ackley_mc::wang_landau::{run_wang_landau, WangLandauResult};
 
 mod ackley_examples;
-mod ackley_mc;
 mod fileio;
 mod plot;
 
@@ -15,40 +27,459 @@ mod plot;
 #[allow(dead_code)]
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
-    if args.filename.is_some() {
-        let param_filename = args.filename.unwrap();
-        let amc_params: AckleyMcParams = fileio::parse_file(param_filename);
+    if let Some(param_filename) = args.filename.clone() {
+        // The command line flag takes precedence over the parameter file.
+        let precision: String = match args.precision.clone() {
+            Some(precision) => precision,
+            None => fileio::parse_precision(param_filename.clone()),
+        };
+        if precision == "f64" {
+            run_amc::<f64>(param_filename, &args);
+        } else {
+            run_amc::<f32>(param_filename, &args);
+        }
+        return Ok(());
+    }
+    if args.aex {
+        println!("\nGenerating pure Ackley function examples...");
+        ackley_examples::generate();
+        return Ok(());
+    }
+    return Err("No parameter file name or 'aex' argument provided.".into());
+}
+
+fn run_amc<F: McFloat>(param_filename: String, args: &Args) {
+    let amc_params: AckleyMcParams<F> = fileio::parse_file(param_filename, args.burn_in, args.thin);
+
+    if !amc_params.sweep.is_empty() {
+        run_amc_sweep(amc_params, args);
+        return;
+    }
+    let objective = objective_from_params(&amc_params).unwrap();
+    if amc_params.mode == "parallel_tempering" {
+        run_amc_tempering(amc_params, objective.as_ref(), args);
+        return;
+    }
+    if amc_params.mode == "population_annealing" {
+        run_amc_population(amc_params, objective.as_ref(), args);
+        return;
+    }
+    if amc_params.mode == "wang_landau" {
+        run_amc_wang_landau(amc_params, objective.as_ref(), args);
+        return;
+    }
+    if amc_params.mode == "basin_hopping" {
+        run_amc_basin_hopping(amc_params, objective.as_ref(), args);
+        return;
+    }
+    if amc_params.mode == "ensemble" {
+        run_amc_ensemble(amc_params, objective.as_ref(), args);
+        return;
+    }
+    if amc_params.trajectory != "memory" {
+        run_amc_streaming(amc_params, objective.as_ref(), args);
+        return;
+    }
+    let res: AckleyMcResult<F> = run_mc(amc_params.clone(), objective.as_ref());
+    print_res_info(&res, objective.as_ref());
+
+    if args.noout {
+        return;
+    }
+
+    fileio::create_out_dir();
+    // Create csv file.
+    fileio::write_res_to_file(res.clone(), amc_params.foutname.clone());
+    if res.t_vals.iter().any(|&t| t != res.t_vals[0]) {
+        let fname_base: &str = amc_params.foutname.strip_suffix(".csv").unwrap();
+        fileio::write_temps_to_file(&res, format!("{}_temps.csv", fname_base));
+    }
 
-        let res: AckleyMcResult = run_ackley_mc(amc_params.clone());
+    if args.justcsv {
+        return;
+    }
+
+    plot::plot_amc_results(amc_params.clone(), res.clone());
+}
 
+/// Runs without keeping the trajectory in memory, so no plots can be made.
+fn run_amc_streaming<F: McFloat>(
+    amc_params: AckleyMcParams<F>,
+    objective: &dyn Objective<F>,
+    args: &Args,
+) {
+    let fname_base: &str = amc_params
+        .foutname
+        .strip_suffix(".csv")
+        .unwrap_or(&amc_params.foutname);
+
+    let res: AckleyMcResult<F> = if args.noout || amc_params.trajectory == "summary" {
+        let mut sink: SummarySink<F> = SummarySink::new();
+        let res = run_mc_with_sink(amc_params.clone(), objective, &mut sink);
         println!(
-            "--> Accepted {} of {} steps.",
-            res.accepted_steps.len() - 1, // correct for accepting initial value
-            res.n_steps
+            "--> Energy mean: {}, variance: {}, range: [{}, {}]",
+            sink.e_mean,
+            sink.e_variance(),
+            sink.e_min,
+            sink.e_max
         );
-        println!("--> Best energy: {}  at {:?}", res.e_best, res.x_best);
+        println!("--> Mean position: {:?}", sink.x_mean);
+        res
+    } else if amc_params.trajectory == "csv" {
+        fileio::create_out_dir();
+        let mut sink = CsvSink::new(fileio::create_out_file(&amc_params.foutname));
+        let res = run_mc_with_sink(amc_params.clone(), objective, &mut sink);
+        println!("CSV data has been saved to {}", &amc_params.foutname);
+        res
+    } else {
+        fileio::create_out_dir();
+        let filename = format!("{}.bin", fname_base);
+        let mut sink = BinarySink::new(fileio::create_out_file(&filename));
+        let res = run_mc_with_sink(amc_params.clone(), objective, &mut sink);
+        println!("Binary trajectory has been saved to {}", &filename);
+        res
+    };
+    print_res_info(&res, objective);
+}
 
-        if args.noout {
-            return Ok(());
-        }
+/// Replica exchange: one csv (and set of plots) per replica, named after
+/// foutname with a `_replica<k>` suffix.
+fn run_amc_tempering<F: McFloat>(
+    amc_params: AckleyMcParams<F>,
+    objective: &dyn Objective<F>,
+    args: &Args,
+) {
+    let res: TemperingResult<F> = run_parallel_tempering(amc_params.clone(), objective);
+    for (k, temp) in res.temps.iter().enumerate() {
+        println!(
+            "--> Replica {} at T = {}: accepted {} of {} steps, best energy {}",
+            k, temp, res.replicas[k].n_accepted, res.replicas[k].n_steps, res.replicas[k].e_best
+        );
+    }
+    for pair in 0..res.swap_attempts.len() {
+        println!(
+            "--> Swaps {} <-> {}: accepted {} of {} ({:.3})",
+            pair,
+            pair + 1,
+            res.swap_accepts[pair],
+            res.swap_attempts[pair],
+            res.swap_rate(pair)
+        );
+    }
+    let best: usize = res.best_replica();
+    println!("--> Best replica: {}", best);
+    print_res_info(&res.replicas[best], objective);
 
-        fileio::create_out_dir();
-        // Create csv file.
-        fileio::write_res_to_file(res.clone(), amc_params.foutname.clone());
+    if args.noout {
+        return;
+    }
 
-        if args.justcsv {
-            return Ok(());
+    fileio::create_out_dir();
+    let fname_base: &str = amc_params
+        .foutname
+        .strip_suffix(".csv")
+        .unwrap_or(&amc_params.foutname);
+    for (k, replica) in res.replicas.iter().enumerate() {
+        let mut replica_params: AckleyMcParams<F> = amc_params.clone();
+        replica_params.foutname = format!("{}_replica{}.csv", fname_base, k);
+        replica_params.ini_temp = res.temps[k];
+        replica_params.final_temp = res.temps[k];
+        replica_params.schedule = "geometric".to_string();
+        replica_params.schedule_table.clear();
+        fileio::write_res_to_file(replica.clone(), replica_params.foutname.clone());
+        if !args.justcsv {
+            plot::plot_amc_results(replica_params, replica.clone());
         }
+    }
+}
 
-        plot::plot_amc_results(amc_params.clone(), res.clone());
-        return Ok(());
+/// Population annealing: the statistics per temperature go to
+/// `<foutname>_population.csv`.
+fn run_amc_population<F: McFloat>(
+    amc_params: AckleyMcParams<F>,
+    objective: &dyn Objective<F>,
+    args: &Args,
+) {
+    let res: PopulationResult<F> = run_population_annealing(amc_params.clone(), objective);
+    let last = res.stages.last().unwrap();
+    println!(
+        "--> Accepted {} of {} walker steps.",
+        res.n_accepted, res.n_steps
+    );
+    println!(
+        "--> Final population: {} walkers from {} of {} families (effectively {:.1})",
+        last.population, last.n_families, amc_params.pa_size, last.effective_families
+    );
+    println!(
+        "--> Final temperature: {}, mean energy: {}, free energy: {}",
+        last.temperature, last.e_mean, last.free_energy
+    );
+    println!("--> Best energy: {}  at {:?}", res.e_best, res.x_best);
+    if let Some((x_min, e_min)) = objective.global_minimum(res.x_best.len()) {
+        println!("--> Global minimum: {}  at {:?}", e_min, x_min);
     }
-    if args.aex {
-        println!("\nGenerating pure Ackley function examples...");
-        ackley_examples::generate();
-        return Ok(());
+
+    if args.noout {
+        return;
+    }
+
+    fileio::create_out_dir();
+    let fname_base: &str = amc_params
+        .foutname
+        .strip_suffix(".csv")
+        .unwrap_or(&amc_params.foutname);
+    fileio::write_population_to_file(&res, format!("{}_population.csv", fname_base));
+}
+
+/// Wang-Landau: ln g(E) goes to `<foutname>_dos.csv` and is plotted; the
+/// canonical averages at ini_temp and final_temp are printed as a check.
+fn run_amc_wang_landau<F: McFloat>(
+    amc_params: AckleyMcParams<F>,
+    objective: &dyn Objective<F>,
+    args: &Args,
+) {
+    let res: WangLandauResult<F> = run_wang_landau(amc_params.clone(), objective);
+    println!("--> Accepted {} of {} steps.", res.n_accepted, res.n_steps);
+    println!(
+        "--> Visited {} of {} energy bins, {} flat histograms, final ln f: {}",
+        res.visited.iter().filter(|&&v| v).count(),
+        res.visited.len(),
+        res.n_flat,
+        res.ln_f
+    );
+    for temp in [amc_params.ini_temp, amc_params.final_temp] {
+        let (e_mean, heat_capacity) = res.canonical_averages(to_f64(temp));
+        println!(
+            "--> At T = {}: mean energy {}, heat capacity {}",
+            temp, e_mean, heat_capacity
+        );
+    }
+    println!("--> Best energy: {}  at {:?}", res.e_best, res.x_best);
+
+    if args.noout {
+        return;
+    }
+
+    fileio::create_out_dir();
+    let fname_base: &str = amc_params
+        .foutname
+        .strip_suffix(".csv")
+        .unwrap_or(&amc_params.foutname);
+    fileio::write_dos_to_file(&res, format!("{}_dos.csv", fname_base));
+
+    if args.justcsv {
+        return;
+    }
+
+    let (energies, ln_g): (Vec<f64>, Vec<f64>) = (0..res.ln_g.len())
+        .filter(|&k| res.visited[k])
+        .map(|k| (res.bin_energy(k), res.ln_g[k]))
+        .unzip();
+    plot::plot_density_of_states(
+        energies,
+        ln_g,
+        format!("{}_dos.png", fname_base).as_str(),
+        format!(
+            "Density of States ({}, {} Dimensions)",
+            res.objective,
+            amc_params.x_ini.len()
+        )
+        .as_str(),
+    );
+}
+
+/// Basin hopping: basins and hops go to `<foutname>_basins.csv` and
+/// `<foutname>_hops.csv`, the energy of the current basin is plotted per hop.
+fn run_amc_basin_hopping<F: McFloat>(
+    amc_params: AckleyMcParams<F>,
+    objective: &dyn Objective<F>,
+    args: &Args,
+) {
+    let res: BasinHoppingResult<F> = run_basin_hopping(amc_params.clone(), objective);
+    println!(
+        "--> Accepted {} of {} hops, visited {} distinct basins.",
+        res.n_accepted,
+        res.hops.len(),
+        res.basins.len()
+    );
+    println!("--> Best energy: {}  at {:?}", res.e_best, res.x_best);
+    if let Some((x_min, e_min)) = objective.global_minimum(res.x_best.len()) {
+        println!("--> Global minimum: {}  at {:?}", e_min, x_min);
+    }
+
+    if args.noout {
+        return;
+    }
+
+    fileio::create_out_dir();
+    let fname_base: &str = amc_params
+        .foutname
+        .strip_suffix(".csv")
+        .unwrap_or(&amc_params.foutname);
+    fileio::write_basins_to_file(
+        &res,
+        format!("{}_basins.csv", fname_base),
+        format!("{}_hops.csv", fname_base),
+    );
+
+    if args.justcsv {
+        return;
+    }
+
+    let sequence: Vec<usize> = res.basin_sequence();
+    plot::plot_energies(
+        (0..sequence.len() as u64).collect(),
+        sequence
+            .iter()
+            .map(|&k| to_f64(res.basins[k].energy))
+            .collect(),
+        vec![],
+        format!("{}_basins.png", fname_base).as_str(),
+        format!(
+            "Basin Energy per Hop ({}, {})",
+            res.objective, amc_params.bh_minimizer
+        )
+        .as_str(),
+    );
+}
+
+/// Ensemble: one csv per run, named after foutname with a `_run<k>` suffix,
+/// the best energy of every run in `<foutname>_ensemble.csv` and its
+/// statistics in `<foutname>_ensemble_summary.csv`. Only the best run is plotted.
+fn run_amc_ensemble<F: McFloat>(
+    amc_params: AckleyMcParams<F>,
+    objective: &dyn Objective<F>,
+    args: &Args,
+) {
+    println!(
+        "--> Running {} chains on {} threads.",
+        amc_params.ens_runs,
+        thread_count(&amc_params, amc_params.ens_runs)
+    );
+    let res: EnsembleResult<F> = run_ensemble(amc_params.clone());
+    println!(
+        "--> Best energy quantiles: min {}, 10% {}, 25% {}, median {}, 75% {}, 90% {}, max {}",
+        res.e_best_quantile(0.0),
+        res.e_best_quantile(0.1),
+        res.e_best_quantile(0.25),
+        res.e_best_quantile(0.5),
+        res.e_best_quantile(0.75),
+        res.e_best_quantile(0.9),
+        res.e_best_quantile(1.0)
+    );
+    println!(
+        "--> Success rate (best energy <= {}): {:.3}",
+        res.e_success,
+        res.success_rate()
+    );
+    let best: usize = res.best_run();
+    println!("--> Best run: {} (seed {})", best, res.seeds[best]);
+    print_res_info(&res.runs[best], objective);
+
+    if args.noout {
+        return;
+    }
+
+    fileio::create_out_dir();
+    let fname_base: &str = amc_params
+        .foutname
+        .strip_suffix(".csv")
+        .unwrap_or(&amc_params.foutname);
+    for (k, run) in res.runs.iter().enumerate() {
+        fileio::write_res_to_file(run.clone(), format!("{}_run{}.csv", fname_base, k));
+    }
+    fileio::write_ensemble_to_file(
+        &res,
+        format!("{}_ensemble.csv", fname_base),
+        format!("{}_ensemble_summary.csv", fname_base),
+    );
+
+    if args.justcsv {
+        return;
+    }
+
+    let mut best_params: AckleyMcParams<F> = amc_params.clone();
+    best_params.foutname = format!("{}_run{}.csv", fname_base, best);
+    best_params.seed = res.seeds[best];
+    plot::plot_amc_results(best_params, res.runs[best].clone());
+}
+
+/// Parameter sweep: one line per point in `<foutname>_sweep.csv` and, with
+/// at least two swept parameters, heat maps of the best and final energy
+/// over the first two in `<foutname>_sweep_best.png` and
+/// `<foutname>_sweep_final.png`, averaged over the others.
+fn run_amc_sweep<F: McFloat>(amc_params: AckleyMcParams<F>, args: &Args) {
+    let n_points: usize = amc_params
+        .sweep
+        .iter()
+        .map(|axis| axis.values.len())
+        .product();
+    println!(
+        "--> Sweeping {} over {} points on {} threads.",
+        amc_params
+            .sweep
+            .iter()
+            .map(|axis| axis.key.as_str())
+            .collect::<Vec<&str>>()
+            .join(", "),
+        n_points,
+        thread_count(&amc_params, n_points)
+    );
+    let res: SweepResult<F> = run_sweep(amc_params.clone());
+    let best = &res.points[res.best_point()];
+    for (axis, value) in res.axes.iter().zip(&best.values) {
+        println!("--> Best point: {} = {}", axis.key, value);
+    }
+    println!("--> Best energy: {}  at {:?}", best.e_best, best.x_best);
+
+    if args.noout {
+        return;
+    }
+
+    fileio::create_out_dir();
+    let fname_base: &str = amc_params
+        .foutname
+        .strip_suffix(".csv")
+        .unwrap_or(&amc_params.foutname);
+    fileio::write_sweep_to_file(&res, format!("{}_sweep.csv", fname_base));
+
+    if args.justcsv || res.axes.len() < 2 {
+        return;
+    }
+
+    let labels = (res.axes[0].key.as_str(), res.axes[1].key.as_str());
+    let (xs, ys, z) = res.heat_map(|p| to_f64(p.e_best));
+    plot::plot_sweep_heat_map(
+        xs,
+        ys,
+        z,
+        labels,
+        format!("{}_sweep_best.png", fname_base).as_str(),
+        "Best Energy",
+    );
+    let (xs, ys, z) = res.heat_map(|p| to_f64(p.e_final));
+    plot::plot_sweep_heat_map(
+        xs,
+        ys,
+        z,
+        labels,
+        format!("{}_sweep_final.png", fname_base).as_str(),
+        "Final Energy",
+    );
+}
+
+fn print_res_info<F: McFloat>(res: &AckleyMcResult<F>, objective: &dyn Objective<F>) {
+    println!("--> Accepted {} of {} steps.", res.n_accepted, res.n_steps);
+    if let Some((_, x_deltas)) = res.x_deltas.last() {
+        println!("--> Adapted step size(s): {:?}", x_deltas);
+    }
+    if !res.reheats.is_empty() {
+        println!("--> Reheated {} times.", res.reheats.len());
+    }
+    println!("--> Best energy: {}  at {:?}", res.e_best, res.x_best);
+    if let Some((x_min, e_min)) = objective.global_minimum(res.x_best.len()) {
+        println!("--> Global minimum: {}  at {:?}", e_min, x_min);
     }
-    return Err("No parameter file name or 'aex' argument provided.".into());
 }
 
 // -------------------- command line parser --------------------
@@ -73,4 +504,16 @@ struct Args {
     /// Generate Ackley example plots + csv (only if no file name provided).
     #[arg(long, default_value_t = false)]
     aex: bool,
+
+    /// Floating point precision (f32 or f64), overrides the parameter file.
+    #[arg(long, value_parser = ["f32", "f64"])]
+    precision: Option<String>,
+
+    /// Number of initial steps not recorded, overrides the parameter file.
+    #[arg(long)]
+    burn_in: Option<u64>,
+
+    /// Record only every n-th step, overrides the parameter file.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    thin: Option<u64>,
 }
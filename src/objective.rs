@@ -0,0 +1,101 @@
+use crate::float::McFloat;
+
+// -------------------- objective trait --------------------
+
+/// A cost function ("energy landscape") the Monte Carlo engine can minimise.
+pub trait Objective<F: McFloat> {
+    /// Energy of point `x`.
+    fn evaluate(&self, x: &[F]) -> F;
+
+    /// Fixed dimension of the function, `None` if it is defined in any dimension.
+    fn dim(&self) -> Option<usize> {
+        return None;
+    }
+
+    /// Documented search domain as a (lower, upper) bound for every dimension, if any.
+    fn domain(&self) -> Option<(F, F)> {
+        return None;
+    }
+
+    /// Known global minimum in `dim` dimensions as (location, energy), if any.
+    fn global_minimum(&self, _dim: usize) -> Option<(Vec<F>, F)> {
+        return None;
+    }
+
+    /// Short name, used in console output and plot titles.
+    fn name(&self) -> String;
+
+    /// Incremental evaluation for single-coordinate moves, if supported.
+    fn delta(&self) -> Option<&dyn DeltaObjective<F>> {
+        return None;
+    }
+
+    /// Writes the gradient at `x` to `grad` and returns true, or returns false
+    /// if no analytic gradient is available. Where the function has a kink
+    /// (e.g. Ackley at the origin) any subgradient will do.
+    fn gradient(&self, _x: &[F], _grad: &mut [F]) -> bool {
+        return false;
+    }
+}
+
+// -------------------- incremental evaluation --------------------
+
+/// An objective that can be computed from a few running sums over all
+/// coordinates, so a move that changes one coordinate is evaluated in O(1)
+/// instead of O(n). Rounding errors accumulate in the sums, so callers should
+/// recompute them from scratch now and then.
+pub trait DeltaObjective<F: McFloat> {
+    /// Number of running sums.
+    fn n_sums(&self) -> usize;
+
+    /// Adds `weight` times the contribution of coordinate `i` with value `xi` to `sums`.
+    fn accumulate(&self, sums: &mut [F], i: usize, xi: F, weight: F);
+
+    /// Energy of a `dim`-dimensional point from its running sums.
+    fn energy_from_sums(&self, sums: &[F], dim: usize) -> F;
+
+    /// Running sums of point `x`, computed from scratch.
+    fn sums(&self, x: &[F]) -> Vec<F> {
+        let mut sums: Vec<F> = vec![F::zero(); self.n_sums()];
+        for (i, &xi) in x.iter().enumerate() {
+            self.accumulate(&mut sums, i, xi, F::one());
+        }
+        return sums;
+    }
+
+    /// Updates `sums` for coordinate `i` changing from `old` to `new`.
+    fn update_sums(&self, sums: &mut [F], i: usize, old: F, new: F) {
+        self.accumulate(sums, i, old, -F::one());
+        self.accumulate(sums, i, new, F::one());
+    }
+}
+
+impl<F: McFloat, O: Objective<F> + ?Sized> Objective<F> for Box<O> {
+    fn evaluate(&self, x: &[F]) -> F {
+        return (**self).evaluate(x);
+    }
+
+    fn dim(&self) -> Option<usize> {
+        return (**self).dim();
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return (**self).domain();
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        return (**self).global_minimum(dim);
+    }
+
+    fn name(&self) -> String {
+        return (**self).name();
+    }
+
+    fn delta(&self) -> Option<&dyn DeltaObjective<F>> {
+        return (**self).delta();
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        return (**self).gradient(x, grad);
+    }
+}
@@ -1,10 +1,11 @@
 use crate::{AckleyMcParams, AckleyMcResult};
-use float_cmp::ApproxEq;
+use ackley_mc::float::{to_f64, McFloat};
 use plotly::{
+    color::NamedColor,
     common::{Marker, Mode, Title},
     histogram::Bins,
-    layout::{Axis, Layout},
-    Histogram, ImageFormat, Plot, Scatter, Scatter3D,
+    layout::{Axis, Layout, Shape, ShapeLayer, ShapeLine, ShapeType},
+    HeatMap, Histogram, ImageFormat, Plot, Scatter, Scatter3D,
 };
 
 // -------------------- plot pure ackley data --------------------
@@ -54,34 +55,79 @@ pub fn plot_ackley_1d(xvals: Vec<f32>, yvals: Vec<f32>, filename: String, title:
         .y_axis(Axis::new().title("y".into()));
     plot.set_layout(layout);
 
-    plot.write_image(filename.clone(), ImageFormat::PNG, 1200, 800, 1.0);
+    plot.write_image(&filename, ImageFormat::PNG, 1200, 800, 1.0);
     println!("Plot has been saved to {}", &filename);
 }
 
 // -------------------- plot ackley mc data --------------------
 
-pub fn plot_amc_results(params: AckleyMcParams, res: AckleyMcResult) {
+pub fn plot_amc_results<F: McFloat>(params: AckleyMcParams<F>, res: AckleyMcResult<F>) {
     let fname_img: &str = params.foutname.strip_suffix(".csv").unwrap();
-    let param_str: String;
-    if params.ini_temp.approx_eq(params.final_temp, (0.0, 2)) {
-        param_str = format!("(x_delta: {}; T: {})", params.x_delta, params.ini_temp);
+    let objective_str: String = if params.function == "ackley" {
+        format!(
+            "{} a: {}, b: {}, c: {:.3}",
+            res.objective, params.ack_a, params.ack_b, params.ack_c
+        )
     } else {
-        param_str = format!(
-            "(x_delta: {}; T: {} -> {})",
-            params.x_delta, params.ini_temp, params.final_temp
-        );
-    }
+        res.objective.clone()
+    };
+    let param_str: String = if !params.schedule_table.is_empty() {
+        format!("({}; x_delta: {}; T: table)", objective_str, params.x_delta)
+    } else if params.schedule != "geometric" {
+        format!(
+            "({}; x_delta: {}; T: {} -> {}, {})",
+            objective_str, params.x_delta, params.ini_temp, params.final_temp, params.schedule
+        )
+    } else if params.ini_temp.approx_eq_ulps(params.final_temp) {
+        format!(
+            "({}; x_delta: {}; T: {})",
+            objective_str, params.x_delta, params.ini_temp
+        )
+    } else {
+        format!(
+            "({}; x_delta: {}; T: {} -> {})",
+            objective_str, params.x_delta, params.ini_temp, params.final_temp
+        )
+    };
 
     // Plot energy.
     plot_energies(
-        res.e_vals.clone(),
+        res.steps.clone(),
+        res.e_vals.iter().map(|&e| to_f64(e)).collect(),
+        res.reheats
+            .iter()
+            .map(|&(step, e)| (step, to_f64(e)))
+            .collect(),
         format!("{}_energies.png", fname_img).as_str(),
         format!("Energy/Cost {}", param_str).as_str(),
     );
 
+    // Plot temperatures, unless constant.
+    if res.t_vals.iter().any(|&t| t != res.t_vals[0]) {
+        plot_temperatures(
+            res.steps.clone(),
+            res.t_vals.iter().map(|&t| to_f64(t)).collect(),
+            format!("{}_temps.png", fname_img).as_str(),
+            format!("Temperature {}", param_str).as_str(),
+        );
+    }
+
+    // Allowed box per dimension, shaded in the x plots.
+    let bounds: Vec<(f64, f64)> = params
+        .lower
+        .iter()
+        .zip(&params.upper)
+        .map(|(&lo, &up)| (to_f64(lo), to_f64(up)))
+        .collect();
+
     // Plot x-coordinates.
     plot_xcoords(
-        res.x_vals.clone(),
+        res.steps.clone(),
+        res.x_vals
+            .iter()
+            .map(|x| x.iter().map(|&xi| to_f64(xi)).collect())
+            .collect(),
+        bounds.clone(),
         format!("{}_xvals.png", fname_img).as_str(),
         format!(
             "X-Trajectory, {} Dimensions {}",
@@ -92,88 +138,208 @@ pub fn plot_amc_results(params: AckleyMcParams, res: AckleyMcResult) {
     );
 
     // Create histogram of x-values for one dimension.
-    let x_1d: Vec<f32> = res
+    let x_1d: Vec<f64> = res
         .x_vals
-        .clone()
         .iter()
-        .map(|s| s[0])
-        .collect::<Vec<f32>>();
+        .map(|s| to_f64(s[0]))
+        .collect::<Vec<f64>>();
 
+    let sampling_str: String = if params.burn_in > 0 || params.thin > 1 {
+        format!(", Burn-In {}, Every {}. Step", params.burn_in, params.thin)
+    } else {
+        String::new()
+    };
     let bins: usize = 50;
     plot_xval_hist(
         x_1d.clone(),
         bins,
+        bounds.first().copied(),
         format!("{}_xhist_{}bins.png", fname_img, bins).as_str(),
         format!(
-            "Histogram of x-Values In One Dimension, {bins} Bins {}",
-            param_str
+            "Histogram of x-Values In One Dimension, {bins} Bins{} {}",
+            sampling_str, param_str
         )
         .as_str(),
     );
 }
 
+/// Energy over steps, with markers at the reheat/restart events (step, energy).
 #[allow(dead_code)]
-pub fn plot_energies(yvals: Vec<f32>, filename: &str, title: &str) {
-    let xvals: Vec<i32> = (0..yvals.len() as i32).collect();
-
-    let trace = Scatter::new(xvals, yvals).mode(Mode::Lines).name("energy");
+pub fn plot_energies(
+    steps: Vec<u64>,
+    yvals: Vec<f64>,
+    events: Vec<(u64, f64)>,
+    filename: &str,
+    title: &str,
+) {
+    let trace = Scatter::new(steps, yvals).mode(Mode::Lines).name("energy");
 
     let mut plot = Plot::new();
     plot.add_trace(trace);
+    if !events.is_empty() {
+        let (event_steps, event_es): (Vec<u64>, Vec<f64>) = events.into_iter().unzip();
+        let event_trace = Scatter::new(event_steps, event_es)
+            .mode(Mode::Markers)
+            .marker(Marker::new().size(10))
+            .name("reheat");
+        plot.add_trace(event_trace);
+    }
 
     let layout = Layout::new()
-        .title(Title::new(&title))
+        .title(Title::new(title))
         .x_axis(Axis::new().title("step".into()))
         .y_axis(Axis::new().title("energy (arb. units)".into()));
     plot.set_layout(layout);
 
-    plot.write_image(filename.clone(), ImageFormat::PNG, 1200, 800, 1.0);
+    plot.write_image(filename, ImageFormat::PNG, 1200, 800, 1.0);
+    println!("Plot has been saved to {}", &filename);
+}
+
+pub fn plot_temperatures(steps: Vec<u64>, yvals: Vec<f64>, filename: &str, title: &str) {
+    let trace = Scatter::new(steps, yvals)
+        .mode(Mode::Lines)
+        .name("temperature");
+
+    let mut plot = Plot::new();
+    plot.add_trace(trace);
+
+    let layout = Layout::new()
+        .title(Title::new(title))
+        .x_axis(Axis::new().title("step".into()))
+        .y_axis(Axis::new().title("temperature (arb. units)".into()));
+    plot.set_layout(layout);
+
+    plot.write_image(filename, ImageFormat::PNG, 1200, 800, 1.0);
+    println!("Plot has been saved to {}", &filename);
+}
+
+/// ln g(E) of a Wang-Landau run over the bin energies.
+pub fn plot_density_of_states(energies: Vec<f64>, ln_g: Vec<f64>, filename: &str, title: &str) {
+    let trace = Scatter::new(energies, ln_g)
+        .mode(Mode::LinesMarkers)
+        .name("ln g(E)");
+
+    let mut plot = Plot::new();
+    plot.add_trace(trace);
+
+    let layout = Layout::new()
+        .title(Title::new(title))
+        .x_axis(Axis::new().title("energy (arb. units)".into()))
+        .y_axis(Axis::new().title("ln g(E)".into()));
+    plot.set_layout(layout);
+
+    plot.write_image(filename, ImageFormat::PNG, 1200, 800, 1.0);
+    println!("Plot has been saved to {}", &filename);
+}
+
+/// `z[y][x]` over the grid of two swept parameters.
+pub fn plot_sweep_heat_map(
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    z: Vec<Vec<f64>>,
+    labels: (&str, &str),
+    filename: &str,
+    title: &str,
+) {
+    let trace = HeatMap::new(xs, ys, z);
+
+    let mut plot = Plot::new();
+    plot.add_trace(trace);
+
+    let layout = Layout::new()
+        .title(Title::new(title))
+        .x_axis(Axis::new().title(labels.0.into()))
+        .y_axis(Axis::new().title(labels.1.into()));
+    plot.set_layout(layout);
+
+    plot.write_image(filename, ImageFormat::PNG, 1200, 800, 1.0);
     println!("Plot has been saved to {}", &filename);
 }
 
 #[allow(dead_code)]
-pub fn plot_xcoords(yvals: Vec<Vec<f32>>, filename: &str, title: &str) {
-    let xvals: Vec<i32> = (0..yvals.len() as i32).collect();
+/// x-coordinates over steps, with the distinct (lower, upper) `bounds` (one
+/// per dimension or none) shaded.
+pub fn plot_xcoords(
+    steps: Vec<u64>,
+    yvals: Vec<Vec<f64>>,
+    bounds: Vec<(f64, f64)>,
+    filename: &str,
+    title: &str,
+) {
     let dim: usize = yvals[0].len();
 
     let mut plot = Plot::new();
     for i in 0..dim {
-        let curr_vals: Vec<f32> = yvals.clone().iter().map(|s| s[i]).collect::<Vec<f32>>();
-        let trace = Scatter::new(xvals.clone(), curr_vals)
+        let curr_vals: Vec<f64> = yvals.iter().map(|s| s[i]).collect::<Vec<f64>>();
+        let trace = Scatter::new(steps.clone(), curr_vals)
             .mode(Mode::Lines)
             .name(format!("x{i}"));
 
         plot.add_trace(trace);
     }
 
-    let layout = Layout::new()
-        .title(Title::new(&title))
+    let mut layout = Layout::new()
+        .title(Title::new(title))
         .x_axis(Axis::new().title("step".into()))
         .y_axis(Axis::new().title("x values".into()));
+    let mut shaded: Vec<(f64, f64)> = vec![];
+    for (lo, up) in bounds {
+        if !shaded.contains(&(lo, up)) {
+            layout.add_shape(bounds_shape(0.0, 1.0, "paper", lo, up, "y"));
+            shaded.push((lo, up));
+        }
+    }
     plot.set_layout(layout);
 
-    plot.write_image(filename.clone(), ImageFormat::PNG, 1200, 800, 1.0);
+    plot.write_image(filename, ImageFormat::PNG, 1200, 800, 1.0);
     println!("Plot has been saved to {}", &filename);
 }
 
-pub fn plot_xval_hist(vals: Vec<f32>, buckets: usize, filename: &str, title: &str) {
-    let min_x = vals.clone().iter().copied().reduce(f32::min).unwrap();
-    let max_x = vals.clone().iter().copied().reduce(f32::max).unwrap();
-    let bucket_size: f32 = (max_x - min_x).abs() / (buckets as f32);
+/// Histogram of `vals`, with the (lower, upper) `bounds` of the dimension shaded.
+pub fn plot_xval_hist(
+    vals: Vec<f64>,
+    buckets: usize,
+    bounds: Option<(f64, f64)>,
+    filename: &str,
+    title: &str,
+) {
+    let min_x = vals.iter().copied().reduce(f64::min).unwrap();
+    let max_x = vals.iter().copied().reduce(f64::max).unwrap();
+    let bucket_size: f64 = (max_x - min_x).abs() / (buckets as f64);
 
     let t = Histogram::new(vals.clone())
         .auto_bin_x(false)
-        .x_bins(Bins::new(min_x as f64, max_x as f64, bucket_size as f64));
+        .x_bins(Bins::new(min_x, max_x, bucket_size));
 
     let mut plot = Plot::new();
     plot.add_trace(t);
 
-    let layout = Layout::new()
-        .title(Title::new(&title))
+    let mut layout = Layout::new()
+        .title(Title::new(title))
         .x_axis(Axis::new().title("x values".into()))
         .y_axis(Axis::new().title("count".into()));
+    if let Some((lo, up)) = bounds {
+        layout.add_shape(bounds_shape(lo, up, "x", 0.0, 1.0, "paper"));
+    }
     plot.set_layout(layout);
 
-    plot.write_image(filename.clone(), ImageFormat::PNG, 1200, 800, 1.0);
+    plot.write_image(filename, ImageFormat::PNG, 1200, 800, 1.0);
     println!("Plot has been saved to {}", &filename);
 }
+
+/// Translucent rectangle from (x0, y0) to (x1, y1), the coordinates referring
+/// to the axis or the plotting area ("paper") as given by x_ref and y_ref.
+fn bounds_shape(x0: f64, x1: f64, x_ref: &str, y0: f64, y1: f64, y_ref: &str) -> Shape {
+    return Shape::new()
+        .shape_type(ShapeType::Rect)
+        .x_ref(x_ref)
+        .x0(x0)
+        .x1(x1)
+        .y_ref(y_ref)
+        .y0(y0)
+        .y1(y1)
+        .fill_color(NamedColor::LightGreen)
+        .opacity(0.2)
+        .layer(ShapeLayer::Below)
+        .line(ShapeLine::new().width(0.0));
+}
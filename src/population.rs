@@ -0,0 +1,294 @@
+use crate::ackley_mc::AckleyMcParams;
+use crate::chain::Chain;
+use crate::float::{to_f64, McFloat};
+use crate::objective::Objective;
+use crate::schedule::{schedule_from_params, CoolingSchedule};
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+// -------------------- population annealing --------------------
+
+/// One walker of the population. `family` is the index of the initial
+/// walker it descends from.
+#[derive(Clone, Debug)]
+struct Walker<F: McFloat> {
+    x: Vec<F>,
+    energy: F,
+    family: usize,
+}
+
+/// Population statistics at one temperature, after resampling and
+/// equilibration.
+#[derive(Clone, Debug)]
+pub struct PopulationStage<F: McFloat = f32> {
+    /// Number of Metropolis steps per walker done so far.
+    pub step: u64,
+    pub temperature: F,
+    pub population: usize,
+    /// Number of surviving families (initial walkers with descendants).
+    pub n_families: usize,
+    /// exp of the entropy of the family sizes, i.e. the number of equally
+    /// large families the population is as diverse as.
+    pub effective_families: f64,
+    pub e_mean: F,
+    /// Free energy estimate -T ln(Z(T) / Z(ini_temp)), relative to the
+    /// initial temperature.
+    pub free_energy: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct PopulationResult<F: McFloat = f32> {
+    pub objective: String,
+    pub stages: Vec<PopulationStage<F>>,
+    pub n_accepted: u64,
+    pub n_steps: u64,
+    pub x_best: Vec<F>,
+    pub e_best: F,
+}
+
+/// Population annealing (Hukushima-Iba, Machta): `pa_size` walkers start at
+/// x_ini and are cooled together along the cooling schedule, which changes
+/// the temperature every `pa_interval` steps. On each change the population
+/// is resampled with the Boltzmann weights exp(-(1 / T_new - 1 / T_old) E),
+/// each walker having floor(tau) or floor(tau) + 1 copies for an expected
+/// number tau = pa_size w / sum(w), so the population size fluctuates around
+/// `pa_size` instead of drifting away from it. Then every walker makes
+/// `pa_interval` Metropolis steps at the new temperature with a chain of its
+/// own, so no adaptive proposal state is shared between walkers. An adaptive
+/// schedule observes the population once per step of all walkers. The run
+/// stops early if the population dies out.
+pub fn run_population_annealing<F: McFloat, O: Objective<F> + ?Sized>(
+    params: AckleyMcParams<F>,
+    objective: &O,
+) -> PopulationResult<F> {
+    let mut schedule: Box<dyn CoolingSchedule<F>> = schedule_from_params(&params)
+        .unwrap_or_else(|| panic!("Unknown cooling schedule '{}'", params.schedule));
+    // the resampling has its own random numbers, the n-th chain uses seed + n
+    let mut rng = ChaCha8Rng::seed_from_u64(params.seed);
+    let mut n_chains: u64 = 0;
+    let mut new_chain = |x: &[F]| {
+        n_chains += 1;
+        return Chain::new(&params, objective, x, params.seed.wrapping_add(n_chains));
+    };
+
+    let mut chains: Vec<Chain<F, O>> = (0..params.pa_size)
+        .map(|_| new_chain(&params.x_ini))
+        .collect();
+    let mut walkers: Vec<Walker<F>> = (0..params.pa_size)
+        .map(|family| Walker {
+            x: params.x_ini.clone(),
+            energy: chains[0].energy,
+            family,
+        })
+        .collect();
+    let mut x_best: Vec<F> = params.x_ini.clone();
+    let mut e_best: F = chains[0].energy;
+    let (mut n_accepted, mut n_steps) = (0, 0);
+
+    let mut stages: Vec<PopulationStage<F>> = vec![];
+    // beta F, relative to the initial temperature
+    let mut beta_free_energy: f64 = 0.0;
+    let mut temperature: F = schedule.temperature(0);
+    let mut step: u64 = 0;
+    while step < params.n_step {
+        let t_new: F = schedule.temperature(step);
+        if t_new != temperature {
+            let d_beta: f64 = 1.0 / to_f64(t_new) - 1.0 / to_f64(temperature);
+            beta_free_energy -= resample(&mut walkers, params.pa_size, d_beta, &mut rng);
+            temperature = t_new;
+            for chain in &chains {
+                n_accepted += chain.n_accepted;
+                n_steps += chain.n_steps;
+            }
+            chains = walkers.iter().map(|w| new_chain(&w.x)).collect();
+            if walkers.is_empty() {
+                break;
+            }
+        }
+
+        let n_sweep: u64 = params.pa_interval.min(params.n_step - step);
+        let n = F::from_usize(chains.len()).unwrap();
+        for k in 0..n_sweep {
+            let mut n_accept: usize = 0;
+            for chain in chains.iter_mut() {
+                if chain.step(temperature) {
+                    n_accept += 1;
+                }
+            }
+            let e_mean: F = chains.iter().map(|c| c.energy).sum::<F>() / n;
+            let e_var: F = chains
+                .iter()
+                .map(|c| (c.energy - e_mean).powi(2))
+                .sum::<F>()
+                / n;
+            let acceptance: F = F::from_usize(n_accept).unwrap() / n;
+            schedule.observe_population(step + k, e_mean, e_var.sqrt(), acceptance);
+        }
+        for (walker, chain) in walkers.iter_mut().zip(&chains) {
+            walker.x.copy_from_slice(&chain.x);
+            walker.energy = chain.energy;
+            if chain.e_best < e_best {
+                e_best = chain.e_best;
+                x_best.copy_from_slice(&chain.x_best);
+            }
+        }
+        step += n_sweep;
+
+        let (n_families, effective_families) = family_diversity(&walkers, params.pa_size);
+        let e_sum: F = walkers.iter().map(|w| w.energy).sum();
+        stages.push(PopulationStage {
+            step,
+            temperature,
+            population: walkers.len(),
+            n_families,
+            effective_families,
+            e_mean: e_sum / n,
+            free_energy: to_f64(temperature) * beta_free_energy,
+        });
+    }
+    for chain in &chains {
+        n_accepted += chain.n_accepted;
+        n_steps += chain.n_steps;
+    }
+
+    return PopulationResult {
+        objective: objective.name(),
+        stages,
+        n_accepted,
+        n_steps,
+        x_best,
+        e_best,
+    };
+}
+
+/// Replaces the population by one resampled toward `target` walkers and
+/// returns ln(Z(beta + d_beta) / Z(beta)), estimated as
+/// ln(mean(exp(-d_beta E))).
+fn resample<F: McFloat>(
+    walkers: &mut Vec<Walker<F>>,
+    target: usize,
+    d_beta: f64,
+    rng: &mut ChaCha8Rng,
+) -> f64 {
+    // shifted by the lowest (when cooling) or highest energy, so that the
+    // weights cannot overflow
+    let energies = walkers.iter().map(|w| to_f64(w.energy));
+    let e_ref: f64 = if d_beta > 0.0 {
+        energies.fold(f64::INFINITY, f64::min)
+    } else {
+        energies.fold(f64::NEG_INFINITY, f64::max)
+    };
+    let weights: Vec<f64> = walkers
+        .iter()
+        .map(|w| (-d_beta * (to_f64(w.energy) - e_ref)).exp())
+        .collect();
+    let w_sum: f64 = weights.iter().sum();
+    let n: f64 = walkers.len() as f64;
+
+    let mut resampled: Vec<Walker<F>> = Vec::with_capacity(target);
+    for (walker, w) in walkers.iter().zip(&weights) {
+        let tau: f64 = target as f64 * w / w_sum;
+        let mut n_copies: usize = tau.floor() as usize;
+        if rng.gen_range(0.0..1.0) < tau - tau.floor() {
+            n_copies += 1;
+        }
+        for _ in 0..n_copies {
+            resampled.push(walker.clone());
+        }
+    }
+    *walkers = resampled;
+    return -d_beta * e_ref + (w_sum / n).ln();
+}
+
+/// Number of families with descendants and exp(-sum p ln p) of the family
+/// fractions p.
+fn family_diversity<F: McFloat>(walkers: &[Walker<F>], n_families: usize) -> (usize, f64) {
+    let mut sizes: Vec<usize> = vec![0; n_families];
+    for walker in walkers {
+        sizes[walker.family] += 1;
+    }
+    let n: f64 = walkers.len() as f64;
+    let entropy: f64 = sizes
+        .iter()
+        .filter(|&&size| size > 0)
+        .map(|&size| {
+            let p: f64 = size as f64 / n;
+            return -p * p.ln();
+        })
+        .sum();
+    return (
+        sizes.iter().filter(|&&size| size > 0).count(),
+        entropy.exp(),
+    );
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_population_annealing() {
+    use crate::ackley_mc::{build_amcparams, Ackley};
+
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.mode = "population_annealing".to_string();
+    params.ini_temp = 1.0;
+    params.final_temp = 0.01;
+    params.n_step = 200;
+    params.pa_size = 50;
+    params.pa_interval = 10;
+    params.x_ini = vec![3.0, -2.0];
+    params.x_delta = 0.5;
+
+    let res = run_population_annealing(params, &Ackley::default());
+    assert_eq!(res.stages.len(), 20);
+    let first = &res.stages[0];
+    let last = res.stages.last().unwrap();
+    assert_eq!(first.population, 50);
+    assert_eq!(first.free_energy, 0.0);
+    assert!(last.population > 0);
+    assert!(last.n_families <= first.n_families);
+    assert!(last.effective_families <= last.n_families as f64 + 1e-9);
+    assert!(last.e_mean < first.e_mean);
+    assert!(res.e_best < 1.0);
+}
+
+#[test]
+fn test_resample_keeps_population_size() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let mut walkers: Vec<Walker<f64>> = (0..20)
+        .map(|family| Walker {
+            x: vec![],
+            energy: family as f64 / 20.0,
+            family,
+        })
+        .collect();
+    let mut sizes: Vec<usize> = vec![];
+    for _ in 0..1000 {
+        resample(&mut walkers, 20, 0.5, &mut rng);
+        sizes.push(walkers.len());
+    }
+    let mean: f64 = sizes.iter().sum::<usize>() as f64 / sizes.len() as f64;
+    assert!((mean - 20.0).abs() < 1.0, "{mean}");
+    assert!(sizes.iter().all(|&n| n > 10 && n < 30));
+}
+
+#[test]
+fn test_population_annealing_adaptive_schedule() {
+    use crate::ackley_mc::{build_amcparams, Ackley};
+
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.mode = "population_annealing".to_string();
+    params.schedule = "adaptive".to_string();
+    params.ini_temp = 1.0;
+    params.final_temp = 0.01;
+    params.n_step = 200;
+    params.pa_size = 50;
+    params.pa_interval = 10;
+    params.x_ini = vec![3.0, -2.0];
+    params.x_delta = 0.5;
+
+    let res = run_population_annealing(params, &Ackley::default());
+    let temps: Vec<f64> = res.stages.iter().map(|s| s.temperature).collect();
+    assert_eq!(temps[0], 1.0);
+    assert!(temps.windows(2).all(|t| t[1] <= t[0]));
+    assert!(*temps.last().unwrap() < 1.0);
+}
@@ -0,0 +1,528 @@
+use crate::ackley_mc::AckleyMcParams;
+use crate::float::McFloat;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Cauchy, Exp1, StandardNormal};
+
+// -------------------- step distributions --------------------
+
+/// Distribution of a single (signed) step, symmetric around 0 and with
+/// width `scale`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepDist {
+    /// uniform in [-scale, scale]
+    Uniform,
+    /// normal with standard deviation `scale`
+    Gaussian,
+    /// Cauchy with half width `scale`, heavy tailed (Szu-Hartley fast annealing)
+    Cauchy,
+    /// Laplace (double exponential) with mean absolute step `scale`
+    Laplace,
+}
+
+/// Names accepted by `StepDist::from_name` (and the `proposal_step` key).
+pub const STEP_DIST_NAMES: [&str; 4] = ["uniform", "gaussian", "cauchy", "laplace"];
+
+impl StepDist {
+    pub fn from_name(name: &str) -> Option<StepDist> {
+        let dist = match name {
+            "uniform" => StepDist::Uniform,
+            "gaussian" => StepDist::Gaussian,
+            "cauchy" => StepDist::Cauchy,
+            "laplace" => StepDist::Laplace,
+            _ => return None,
+        };
+        return Some(dist);
+    }
+
+    #[inline]
+    pub fn sample<F: McFloat>(&self, scale: F, rng: &mut ChaCha8Rng) -> F {
+        return match self {
+            StepDist::Uniform => {
+                scale * (F::lit(2.0) * rng.gen_range(F::zero()..F::one()) - F::one())
+            }
+            StepDist::Gaussian => scale * F::lit(rng.sample(StandardNormal)),
+            StepDist::Cauchy => {
+                let cauchy = Cauchy::new(0.0, 1.0).unwrap();
+                scale * F::lit(rng.sample(cauchy))
+            }
+            StepDist::Laplace => {
+                let magnitude: f64 = rng.sample(Exp1);
+                if rng.gen::<bool>() {
+                    scale * F::lit(magnitude)
+                } else {
+                    -scale * F::lit(magnitude)
+                }
+            }
+        };
+    }
+}
+
+// -------------------- proposals --------------------
+
+/// Generates trial moves. Proposals must be symmetric (the probability to
+/// propose y from x equals the one to propose x from y), as the acceptance
+/// criterion has no Hastings correction.
+pub trait Proposal<F: McFloat> {
+    /// Moves `x` to the trial point in place and appends (index, old value)
+    /// of every changed coordinate to `changed` (cleared by the caller), so
+    /// the move can be undone and energies updated incrementally.
+    /// `x_deltas` holds one step size or one per dimension, all multiplied
+    /// by `scale`.
+    fn propose(
+        &mut self,
+        x: &mut [F],
+        x_deltas: &[F],
+        scale: F,
+        rng: &mut ChaCha8Rng,
+        changed: &mut Vec<(usize, F)>,
+    );
+
+    /// Called after every step with the current state of the chain (also if
+    /// the move was rejected). Only adaptive proposals care.
+    fn observe(&mut self, _x: &[F]) {}
+
+    fn name(&self) -> String;
+}
+
+/// Names accepted by `proposal_from_params` (and the `proposal` parameter file
+/// key). The first four change one random coordinate with the respective step
+/// distribution, the others use the `proposal_step` distribution.
+pub const PROPOSAL_NAMES: [&str; 8] = [
+    "uniform",
+    "gaussian",
+    "cauchy",
+    "laplace",
+    "all",
+    "direction",
+    "subset",
+    "adaptive_metropolis",
+];
+
+pub fn proposal_from_params<F: McFloat>(
+    params: &AckleyMcParams<F>,
+) -> Option<Box<dyn Proposal<F>>> {
+    let dim: usize = params.x_ini.len();
+    let step_dist: StepDist = StepDist::from_name(&params.proposal_step)?;
+    let proposal: Box<dyn Proposal<F>> = match params.proposal.as_str() {
+        "all" => Box::new(AllCoordinates::new(step_dist)),
+        "direction" => Box::new(RandomDirection::new(step_dist, dim)),
+        "subset" => Box::new(RandomSubset::new(step_dist, dim, params.proposal_subset)),
+        "adaptive_metropolis" => Box::new(AdaptiveMetropolis::new(
+            dim,
+            params.am_start,
+            params.am_epsilon,
+        )),
+        name => Box::new(SingleCoordinate::new(StepDist::from_name(name)?)),
+    };
+    return Some(proposal);
+}
+
+#[inline]
+fn step_size<F: McFloat>(x_deltas: &[F], i: usize) -> F {
+    if x_deltas.len() == 1 {
+        return x_deltas[0];
+    }
+    return x_deltas[i];
+}
+
+/// Changes one random coordinate.
+#[derive(Clone, Copy, Debug)]
+pub struct SingleCoordinate {
+    pub dist: StepDist,
+}
+
+impl SingleCoordinate {
+    pub fn new(dist: StepDist) -> Self {
+        return SingleCoordinate { dist };
+    }
+}
+
+impl<F: McFloat> Proposal<F> for SingleCoordinate {
+    #[inline]
+    fn propose(
+        &mut self,
+        x: &mut [F],
+        x_deltas: &[F],
+        scale: F,
+        rng: &mut ChaCha8Rng,
+        changed: &mut Vec<(usize, F)>,
+    ) {
+        let idim = rng.gen_range(0..x.len() as u32) as usize;
+        let step: F = self.dist.sample(scale * step_size(x_deltas, idim), rng);
+        changed.push((idim, x[idim]));
+        x[idim] += step;
+    }
+
+    fn name(&self) -> String {
+        return format!("{:?}", self.dist).to_lowercase();
+    }
+}
+
+/// Changes all coordinates at once, independently.
+#[derive(Clone, Copy, Debug)]
+pub struct AllCoordinates {
+    pub dist: StepDist,
+}
+
+impl AllCoordinates {
+    pub fn new(dist: StepDist) -> Self {
+        return AllCoordinates { dist };
+    }
+}
+
+impl<F: McFloat> Proposal<F> for AllCoordinates {
+    fn propose(
+        &mut self,
+        x: &mut [F],
+        x_deltas: &[F],
+        scale: F,
+        rng: &mut ChaCha8Rng,
+        changed: &mut Vec<(usize, F)>,
+    ) {
+        for (i, xi) in x.iter_mut().enumerate() {
+            changed.push((i, *xi));
+            *xi += self.dist.sample(scale * step_size(x_deltas, i), rng);
+        }
+    }
+
+    fn name(&self) -> String {
+        return format!("all ({:?})", self.dist).to_lowercase();
+    }
+}
+
+/// Hit-and-run: moves along a uniformly random direction, with a step
+/// length from `dist` (stretched per dimension by the step sizes).
+#[derive(Clone, Debug)]
+pub struct RandomDirection {
+    pub dist: StepDist,
+    direction: Vec<f64>,
+}
+
+impl RandomDirection {
+    pub fn new(dist: StepDist, dim: usize) -> Self {
+        return RandomDirection {
+            dist,
+            direction: vec![0.0; dim],
+        };
+    }
+}
+
+impl<F: McFloat> Proposal<F> for RandomDirection {
+    fn propose(
+        &mut self,
+        x: &mut [F],
+        x_deltas: &[F],
+        scale: F,
+        rng: &mut ChaCha8Rng,
+        changed: &mut Vec<(usize, F)>,
+    ) {
+        // Normalised standard normal vectors are uniform on the unit sphere.
+        let mut norm: f64 = 0.0;
+        for di in self.direction.iter_mut() {
+            *di = rng.sample(StandardNormal);
+            norm += *di * *di;
+        }
+        let norm: F = F::lit(norm.sqrt());
+        let length: F = self.dist.sample(scale, rng);
+        for (i, xi) in x.iter_mut().enumerate() {
+            changed.push((i, *xi));
+            *xi += length * step_size(x_deltas, i) * F::lit(self.direction[i]) / norm;
+        }
+    }
+
+    fn name(&self) -> String {
+        return format!("direction ({:?})", self.dist).to_lowercase();
+    }
+}
+
+/// Changes `size` distinct random coordinates, independently.
+#[derive(Clone, Debug)]
+pub struct RandomSubset {
+    pub dist: StepDist,
+    pub size: usize,
+    // a permutation of the coordinate indices, partially reshuffled per move
+    indices: Vec<usize>,
+}
+
+impl RandomSubset {
+    pub fn new(dist: StepDist, dim: usize, size: usize) -> Self {
+        assert!(
+            size > 0 && size <= dim,
+            "Subset size must be between 1 and the dimension"
+        );
+        return RandomSubset {
+            dist,
+            size,
+            indices: (0..dim).collect(),
+        };
+    }
+}
+
+impl<F: McFloat> Proposal<F> for RandomSubset {
+    fn propose(
+        &mut self,
+        x: &mut [F],
+        x_deltas: &[F],
+        scale: F,
+        rng: &mut ChaCha8Rng,
+        changed: &mut Vec<(usize, F)>,
+    ) {
+        // partial Fisher-Yates shuffle: the first `size` indices are the subset
+        let dim = self.indices.len();
+        for k in 0..self.size {
+            let j = rng.gen_range(k..dim);
+            self.indices.swap(k, j);
+            let i = self.indices[k];
+            changed.push((i, x[i]));
+            x[i] += self.dist.sample(scale * step_size(x_deltas, i), rng);
+        }
+    }
+
+    fn name(&self) -> String {
+        return format!("subset of {} ({:?})", self.size, self.dist).to_lowercase();
+    }
+}
+
+/// Haario et al.'s adaptive Metropolis: all coordinates move at once, drawn
+/// from N(0, s_d (C + epsilon I)) with s_d = 2.38^2 / d and C the empirical
+/// covariance of all states of the chain so far (the same as the covariance
+/// of `AckleyMcResult::x_vals` without thinning, but accumulated on the fly so
+/// it also works with streaming sinks). During the first `start` steps the
+/// proposal is N(0, x_delta^2 I).
+///
+/// A Cholesky factor L of the scatter matrix S of the states is kept up to
+/// date with one O(d^2) rank-one update per step, so C = L L^T / n. L starts
+/// at zero and never contains epsilon: the epsilon I part of the proposal is
+/// a separate N(0, s_d epsilon I) draw added to the L part.
+#[derive(Clone, Debug)]
+pub struct AdaptiveMetropolis<F: McFloat> {
+    pub dim: usize,
+    pub start: u64,
+    pub epsilon: F,
+    n: u64,
+    mean: Vec<F>,
+    // lower triangular, row-major
+    chol: Vec<F>,
+    // scratch vectors for the rank-one update and the normal draws
+    work: Vec<F>,
+    normals: Vec<F>,
+}
+
+impl<F: McFloat> AdaptiveMetropolis<F> {
+    pub fn new(dim: usize, start: u64, epsilon: F) -> Self {
+        assert!(epsilon > F::zero(), "Adaptive Metropolis needs epsilon > 0");
+        return AdaptiveMetropolis {
+            dim,
+            start,
+            epsilon,
+            n: 0,
+            mean: vec![F::zero(); dim],
+            chol: vec![F::zero(); dim * dim],
+            work: vec![F::zero(); dim],
+            normals: vec![F::zero(); dim],
+        };
+    }
+
+    /// Current estimate of the covariance, L L^T / n.
+    pub fn covariance(&self) -> Vec<Vec<F>> {
+        let d = self.dim;
+        let n = F::from_u64(self.n.max(1)).unwrap();
+        return (0..d)
+            .map(|i| {
+                (0..d)
+                    .map(|j| {
+                        let lij: F = (0..=i.min(j))
+                            .map(|k| self.chol[i * d + k] * self.chol[j * d + k])
+                            .sum();
+                        lij / n
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+
+    /// L L^T + w w^T = L' L'^T, overwriting `work` (holding w). Each column
+    /// of L is rotated against w with a Givens rotation, which also works
+    /// while L is still singular (zero diagonal entries).
+    fn cholesky_rank_one_update(&mut self) {
+        let d = self.dim;
+        for k in 0..d {
+            let lkk: F = self.chol[k * d + k];
+            let r: F = lkk.hypot(self.work[k]);
+            if r == F::zero() {
+                continue;
+            }
+            let (c, s) = (lkk / r, self.work[k] / r);
+            self.chol[k * d + k] = r;
+            for i in (k + 1)..d {
+                let lik: F = self.chol[i * d + k];
+                self.chol[i * d + k] = c * lik + s * self.work[i];
+                self.work[i] = c * self.work[i] - s * lik;
+            }
+        }
+    }
+}
+
+impl<F: McFloat> Proposal<F> for AdaptiveMetropolis<F> {
+    fn propose(
+        &mut self,
+        x: &mut [F],
+        x_deltas: &[F],
+        scale: F,
+        rng: &mut ChaCha8Rng,
+        changed: &mut Vec<(usize, F)>,
+    ) {
+        if self.n < self.start.max(2) {
+            for (i, xi) in x.iter_mut().enumerate() {
+                changed.push((i, *xi));
+                *xi += StepDist::Gaussian.sample(scale * step_size(x_deltas, i), rng);
+            }
+            return;
+        }
+
+        let d = self.dim;
+        let s_d: F = F::lit(2.38 * 2.38) / F::from_usize(d).unwrap();
+        let chol_scale: F = scale * (s_d / F::from_u64(self.n).unwrap()).sqrt();
+        let eps_scale: F = scale * (s_d * self.epsilon).sqrt();
+        for u in self.normals.iter_mut() {
+            *u = F::lit(rng.sample(StandardNormal));
+        }
+        for (i, xi) in x.iter_mut().enumerate() {
+            let lu: F = (0..=i)
+                .map(|k| self.chol[i * d + k] * self.normals[k])
+                .sum();
+            let v: F = F::lit(rng.sample(StandardNormal));
+            changed.push((i, *xi));
+            *xi += chol_scale * lu + eps_scale * v;
+        }
+    }
+
+    fn observe(&mut self, x: &[F]) {
+        // Welford: S_n = S_(n-1) + (n-1)/n (x - m_(n-1)) (x - m_(n-1))^T
+        self.n += 1;
+        let n = F::from_u64(self.n).unwrap();
+        let weight: F = ((n - F::one()) / n).sqrt();
+        for ((&xi, mean), w) in x.iter().zip(self.mean.iter_mut()).zip(self.work.iter_mut()) {
+            let diff: F = xi - *mean;
+            *w = weight * diff;
+            *mean += diff / n;
+        }
+        if self.n > 1 {
+            self.cholesky_rank_one_update();
+        }
+    }
+
+    fn name(&self) -> String {
+        return "adaptive metropolis".to_string();
+    }
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_proposals_report_changed_coordinates() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5);
+    let proposals: Vec<Box<dyn Proposal<f64>>> = vec![
+        Box::new(SingleCoordinate::new(StepDist::Cauchy)),
+        Box::new(AllCoordinates::new(StepDist::Laplace)),
+        Box::new(RandomDirection::new(StepDist::Gaussian, 6)),
+        Box::new(RandomSubset::new(StepDist::Uniform, 6, 3)),
+    ];
+    for (mut proposal, n_changed) in proposals.into_iter().zip([1, 6, 6, 3]) {
+        let x_ini: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let mut x = x_ini.clone();
+        let mut changed: Vec<(usize, f64)> = vec![];
+        proposal.propose(&mut x, &[0.5], 1.0, &mut rng, &mut changed);
+        assert_eq!(changed.len(), n_changed, "{}", proposal.name());
+
+        let mut indices: Vec<usize> = changed.iter().map(|&(i, _)| i).collect();
+        indices.sort();
+        indices.dedup();
+        assert_eq!(indices.len(), n_changed);
+        for &(i, x_old) in &changed {
+            assert_eq!(x_old, x_ini[i]);
+            x[i] = x_old;
+        }
+        assert_eq!(x, x_ini);
+    }
+}
+
+#[test]
+fn test_adaptive_metropolis_covariance_update() {
+    let mut rng = ChaCha8Rng::seed_from_u64(11);
+    let mut am: AdaptiveMetropolis<f64> = AdaptiveMetropolis::new(3, 10, 1e-6);
+    let points: Vec<Vec<f64>> = (0..200)
+        .map(|_| {
+            let a: f64 = rng.sample(StandardNormal);
+            let b: f64 = rng.sample(StandardNormal);
+            vec![a, 2.0 * a + 0.5 * b, -b]
+        })
+        .collect();
+    for p in &points {
+        am.observe(p);
+    }
+
+    // direct population covariance
+    let n = points.len() as f64;
+    let mean: Vec<f64> = (0..3)
+        .map(|i| points.iter().map(|p| p[i]).sum::<f64>() / n)
+        .collect();
+    let cov = am.covariance();
+    for i in 0..3 {
+        for j in 0..3 {
+            let direct: f64 = points
+                .iter()
+                .map(|p| (p[i] - mean[i]) * (p[j] - mean[j]))
+                .sum::<f64>()
+                / n;
+            assert!((cov[i][j] - direct).abs() < 1e-6, "{i} {j}");
+        }
+    }
+}
+
+#[test]
+fn test_adaptive_metropolis_proposal_covariance() {
+    // proposals from a fixed point must have covariance s_d (C + epsilon I)
+    let mut rng = ChaCha8Rng::seed_from_u64(12);
+    let epsilon: f64 = 1.0;
+    let mut am: AdaptiveMetropolis<f64> = AdaptiveMetropolis::new(2, 5, epsilon);
+    let points: Vec<[f64; 2]> = vec![[0.0, 1.0], [1.0, 0.5], [2.0, 3.0], [-1.0, 0.0], [0.5, -1.5]];
+    for p in &points {
+        am.observe(p);
+    }
+    let n = points.len() as f64;
+    let mean: Vec<f64> = (0..2)
+        .map(|i| points.iter().map(|p| p[i]).sum::<f64>() / n)
+        .collect();
+    let s_d: f64 = 2.38 * 2.38 / 2.0;
+    let expected = |i: usize, j: usize| {
+        let c: f64 = points
+            .iter()
+            .map(|p| (p[i] - mean[i]) * (p[j] - mean[j]))
+            .sum::<f64>()
+            / n;
+        return s_d * (c + if i == j { epsilon } else { 0.0 });
+    };
+
+    let n_draws: usize = 100000;
+    let mut sums: [[f64; 2]; 2] = [[0.0; 2]; 2];
+    let mut changed: Vec<(usize, f64)> = vec![];
+    for _ in 0..n_draws {
+        let mut x: Vec<f64> = vec![0.0; 2];
+        changed.clear();
+        am.propose(&mut x, &[1.0], 1.0, &mut rng, &mut changed);
+        for (row, xi) in sums.iter_mut().zip(&x) {
+            for (sum, xj) in row.iter_mut().zip(&x) {
+                *sum += xi * xj;
+            }
+        }
+    }
+    for (i, row) in sums.iter().enumerate() {
+        for (j, sum) in row.iter().enumerate() {
+            let empirical: f64 = sum / n_draws as f64;
+            let tolerance: f64 = 0.02 * (expected(i, i) * expected(j, j)).sqrt();
+            assert!((empirical - expected(i, j)).abs() < tolerance, "{i} {j}");
+        }
+    }
+}
@@ -0,0 +1,501 @@
+use crate::ackley_mc::AckleyMcParams;
+use crate::float::McFloat;
+
+// -------------------- cooling schedules --------------------
+
+/// Temperature as a function of the step index. `temperature(k)` is used to
+/// decide on the k-th trial move (0-based), so k runs from 0 to n_step - 1.
+pub trait CoolingSchedule<F: McFloat> {
+    fn temperature(&self, step: u64) -> F;
+
+    /// Called after the k-th trial move with the energy of the current state
+    /// and whether the move was accepted. Only adaptive schedules care.
+    fn observe(&mut self, _step: u64, _energy: F, _accepted: bool) {}
+
+    /// Population counterpart of `observe`, called once per step of all
+    /// walkers with the mean and standard deviation of their energies and
+    /// the fraction of accepted moves.
+    fn observe_population(&mut self, _step: u64, _e_mean: F, _e_std: F, _acceptance: F) {}
+
+    /// Called when the annealing starts over after a reheat, from then on
+    /// `temperature` is asked for steps counted from 0 again.
+    fn reset(&mut self) {}
+
+    fn name(&self) -> String;
+}
+
+/// Names accepted by `schedule_from_params` (and the `schedule` parameter file key).
+pub const SCHEDULE_NAMES: [&str; 9] = [
+    "geometric",
+    "linear",
+    "logarithmic",
+    "exponential",
+    "inverse",
+    "lundy_mees",
+    "staircase",
+    "adaptive",
+    "tsallis",
+];
+
+/// Builds the schedule selected in `params`. A `schedule_table` (loaded from
+/// the `schedule_file` key) takes precedence over the `schedule` name.
+pub fn schedule_from_params<F: McFloat>(
+    params: &AckleyMcParams<F>,
+) -> Option<Box<dyn CoolingSchedule<F>>> {
+    if !params.schedule_table.is_empty() {
+        return Some(Box::new(Table::new(params.schedule_table.clone())));
+    }
+    let (t_ini, t_final, n_step) = (params.ini_temp, params.final_temp, params.n_step);
+    let schedule: Box<dyn CoolingSchedule<F>> = match params.schedule.as_str() {
+        "geometric" => Box::new(Geometric::new(t_ini, t_final, n_step)),
+        "linear" => Box::new(Linear::new(t_ini, t_final, n_step)),
+        "logarithmic" => Box::new(Logarithmic::new(t_ini)),
+        "exponential" => Box::new(Exponential::new(t_ini, t_final, n_step, params.x_ini.len())),
+        "inverse" => Box::new(Inverse::new(t_ini)),
+        "lundy_mees" => Box::new(LundyMees::new(t_ini, t_final, n_step)),
+        "staircase" => Box::new(Staircase::new(
+            t_ini,
+            t_final,
+            n_step,
+            params.schedule_stairs,
+        )),
+        "adaptive" => Box::new(Adaptive::new(
+            t_ini,
+            t_final,
+            params.schedule_window,
+            params.schedule_lambda,
+        )),
+        "tsallis" => Box::new(Tsallis::new(t_ini, params.tsallis_q_v)),
+        _ => return None,
+    };
+    return Some(schedule);
+}
+
+/// T_k = T_0 (T_f / T_0)^(k / n), i.e. multiplied by the same factor each step.
+#[derive(Clone, Copy, Debug)]
+pub struct Geometric<F: McFloat> {
+    pub t_ini: F,
+    pub t_final: F,
+    pub n_step: u64,
+}
+
+impl<F: McFloat> Geometric<F> {
+    pub fn new(t_ini: F, t_final: F, n_step: u64) -> Self {
+        return Geometric {
+            t_ini,
+            t_final,
+            n_step,
+        };
+    }
+}
+
+impl<F: McFloat> CoolingSchedule<F> for Geometric<F> {
+    #[inline]
+    fn temperature(&self, step: u64) -> F {
+        let frac = F::from_u64(step).unwrap() / F::from_u64(self.n_step).unwrap();
+        return self.t_ini * (self.t_final / self.t_ini).powf(frac);
+    }
+
+    fn name(&self) -> String {
+        return "geometric".to_string();
+    }
+}
+
+/// T_k = T_0 + (T_f - T_0) k / n.
+#[derive(Clone, Copy, Debug)]
+pub struct Linear<F: McFloat> {
+    pub t_ini: F,
+    pub t_final: F,
+    pub n_step: u64,
+}
+
+impl<F: McFloat> Linear<F> {
+    pub fn new(t_ini: F, t_final: F, n_step: u64) -> Self {
+        return Linear {
+            t_ini,
+            t_final,
+            n_step,
+        };
+    }
+}
+
+impl<F: McFloat> CoolingSchedule<F> for Linear<F> {
+    #[inline]
+    fn temperature(&self, step: u64) -> F {
+        let frac = F::from_u64(step).unwrap() / F::from_u64(self.n_step).unwrap();
+        return self.t_ini + (self.t_final - self.t_ini) * frac;
+    }
+
+    fn name(&self) -> String {
+        return "linear".to_string();
+    }
+}
+
+/// Geman & Geman: T_k = T_0 ln(2) / ln(k + 2). Cools too slowly to reach a
+/// given final temperature in practice, so the final temperature is ignored.
+#[derive(Clone, Copy, Debug)]
+pub struct Logarithmic<F: McFloat> {
+    pub t_ini: F,
+}
+
+impl<F: McFloat> Logarithmic<F> {
+    pub fn new(t_ini: F) -> Self {
+        return Logarithmic { t_ini };
+    }
+}
+
+impl<F: McFloat> CoolingSchedule<F> for Logarithmic<F> {
+    #[inline]
+    fn temperature(&self, step: u64) -> F {
+        let k = F::from_u64(step).unwrap();
+        return self.t_ini * F::LN_2() / (k + F::lit(2.0)).ln();
+    }
+
+    fn name(&self) -> String {
+        return "logarithmic".to_string();
+    }
+}
+
+/// Ingber's exponential schedule: T_k = T_0 exp(-c k^(1/D)) for D dimensions,
+/// with c chosen such that T_n = T_f.
+#[derive(Clone, Copy, Debug)]
+pub struct Exponential<F: McFloat> {
+    pub t_ini: F,
+    pub rate: F,
+    pub inv_dim: F,
+}
+
+impl<F: McFloat> Exponential<F> {
+    pub fn new(t_ini: F, t_final: F, n_step: u64, dim: usize) -> Self {
+        let inv_dim = F::one() / F::from_usize(dim).unwrap();
+        let rate = (t_ini / t_final).ln() / F::from_u64(n_step).unwrap().powf(inv_dim);
+        return Exponential {
+            t_ini,
+            rate,
+            inv_dim,
+        };
+    }
+}
+
+impl<F: McFloat> CoolingSchedule<F> for Exponential<F> {
+    #[inline]
+    fn temperature(&self, step: u64) -> F {
+        let k = F::from_u64(step).unwrap();
+        return self.t_ini * (-self.rate * k.powf(self.inv_dim)).exp();
+    }
+
+    fn name(&self) -> String {
+        return "exponential".to_string();
+    }
+}
+
+/// Tsallis & Stariolo's generalised simulated annealing:
+/// T_k = T_0 (2^(q_v - 1) - 1) / ((2 + k)^(q_v - 1) - 1) for the visiting
+/// parameter q_v > 1. q_v -> 1 gives the logarithmic, q_v = 2 the inverse
+/// schedule. The final temperature is ignored.
+#[derive(Clone, Copy, Debug)]
+pub struct Tsallis<F: McFloat> {
+    pub t_ini: F,
+    pub q_v: F,
+}
+
+impl<F: McFloat> Tsallis<F> {
+    pub fn new(t_ini: F, q_v: F) -> Self {
+        return Tsallis { t_ini, q_v };
+    }
+}
+
+impl<F: McFloat> CoolingSchedule<F> for Tsallis<F> {
+    #[inline]
+    fn temperature(&self, step: u64) -> F {
+        let k = F::from_u64(step).unwrap();
+        let exponent: F = self.q_v - F::one();
+        let numerator: F = F::lit(2.0).powf(exponent) - F::one();
+        return self.t_ini * numerator / ((k + F::lit(2.0)).powf(exponent) - F::one());
+    }
+
+    fn name(&self) -> String {
+        return format!("tsallis (q_v = {})", self.q_v);
+    }
+}
+
+/// Szu & Hartley's fast annealing: T_k = T_0 / (1 + k). The final
+/// temperature is ignored.
+#[derive(Clone, Copy, Debug)]
+pub struct Inverse<F: McFloat> {
+    pub t_ini: F,
+}
+
+impl<F: McFloat> Inverse<F> {
+    pub fn new(t_ini: F) -> Self {
+        return Inverse { t_ini };
+    }
+}
+
+impl<F: McFloat> CoolingSchedule<F> for Inverse<F> {
+    #[inline]
+    fn temperature(&self, step: u64) -> F {
+        return self.t_ini / (F::one() + F::from_u64(step).unwrap());
+    }
+
+    fn name(&self) -> String {
+        return "inverse".to_string();
+    }
+}
+
+/// Lundy & Mees: T_(k+1) = T_k / (1 + beta T_k), i.e. 1/T_k = 1/T_0 + beta k,
+/// with beta chosen such that T_n = T_f.
+#[derive(Clone, Copy, Debug)]
+pub struct LundyMees<F: McFloat> {
+    pub t_ini: F,
+    pub beta: F,
+}
+
+impl<F: McFloat> LundyMees<F> {
+    pub fn new(t_ini: F, t_final: F, n_step: u64) -> Self {
+        let beta = (F::one() / t_final - F::one() / t_ini) / F::from_u64(n_step).unwrap();
+        return LundyMees { t_ini, beta };
+    }
+}
+
+impl<F: McFloat> CoolingSchedule<F> for LundyMees<F> {
+    #[inline]
+    fn temperature(&self, step: u64) -> F {
+        return self.t_ini / (F::one() + self.beta * self.t_ini * F::from_u64(step).unwrap());
+    }
+
+    fn name(&self) -> String {
+        return "lundy_mees".to_string();
+    }
+}
+
+/// Piecewise constant: `n_stairs` equally long stairs whose temperatures
+/// decrease geometrically from T_0 (first stair) to T_f (last stair).
+#[derive(Clone, Copy, Debug)]
+pub struct Staircase<F: McFloat> {
+    pub t_ini: F,
+    pub t_final: F,
+    pub n_step: u64,
+    pub n_stairs: u64,
+}
+
+impl<F: McFloat> Staircase<F> {
+    pub fn new(t_ini: F, t_final: F, n_step: u64, n_stairs: u64) -> Self {
+        assert!(n_stairs > 0, "A staircase needs at least one stair");
+        return Staircase {
+            t_ini,
+            t_final,
+            n_step,
+            n_stairs,
+        };
+    }
+}
+
+impl<F: McFloat> CoolingSchedule<F> for Staircase<F> {
+    #[inline]
+    fn temperature(&self, step: u64) -> F {
+        if self.n_stairs == 1 {
+            return self.t_ini;
+        }
+        let stair: u64 = (step * self.n_stairs / self.n_step).min(self.n_stairs - 1);
+        let frac = F::from_u64(stair).unwrap() / F::from_u64(self.n_stairs - 1).unwrap();
+        return self.t_ini * (self.t_final / self.t_ini).powf(frac);
+    }
+
+    fn name(&self) -> String {
+        return "staircase".to_string();
+    }
+}
+
+/// User-supplied (step, temperature) pairs, sorted by step. Each temperature
+/// holds from its step until the next entry; the first one also before.
+#[derive(Clone, Debug)]
+pub struct Table<F: McFloat> {
+    pub entries: Vec<(u64, F)>,
+}
+
+impl<F: McFloat> Table<F> {
+    pub fn new(entries: Vec<(u64, F)>) -> Self {
+        assert!(
+            !entries.is_empty(),
+            "A schedule table needs at least one entry"
+        );
+        assert!(
+            entries.windows(2).all(|w| w[0].0 < w[1].0),
+            "Schedule table steps must be strictly increasing"
+        );
+        return Table { entries };
+    }
+}
+
+impl<F: McFloat> CoolingSchedule<F> for Table<F> {
+    #[inline]
+    fn temperature(&self, step: u64) -> F {
+        // index of the first entry starting after `step`
+        let i: usize = self.entries.partition_point(|&(s, _)| s <= step);
+        return self.entries[i.saturating_sub(1)].1;
+    }
+
+    fn name(&self) -> String {
+        return "table".to_string();
+    }
+}
+
+/// Lam & Delosme's adaptive schedule. After every `window` steps the inverse
+/// temperature s = 1/T is raised by
+///
+///   window * lambda / sigma * 1 / (s sigma)^2 * 4 rho (1 - rho)^2 / (2 - rho)^2,
+///
+/// with sigma the energy standard deviation and rho the acceptance rate over
+/// the window, so it cools slowly where the energy fluctuates strongly
+/// (near phase transitions) and fast where nothing happens. Smaller lambda
+/// means slower cooling. The temperature never drops below `t_final`.
+/// In population annealing sigma and rho come from the population instead,
+/// with one update (window 1) per step of all walkers.
+#[derive(Clone, Debug)]
+pub struct Adaptive<F: McFloat> {
+    pub t_ini: F,
+    pub temp: F,
+    pub t_final: F,
+    pub window: u64,
+    pub lambda: F,
+    // statistics of the current window
+    n: u64,
+    n_accepted: u64,
+    e_mean: F,
+    m2: F,
+}
+
+impl<F: McFloat> Adaptive<F> {
+    pub fn new(t_ini: F, t_final: F, window: u64, lambda: F) -> Self {
+        assert!(
+            window > 1,
+            "The adaptive schedule window needs at least 2 steps"
+        );
+        return Adaptive {
+            t_ini,
+            temp: t_ini,
+            t_final,
+            window,
+            lambda,
+            n: 0,
+            n_accepted: 0,
+            e_mean: F::zero(),
+            m2: F::zero(),
+        };
+    }
+
+    /// Raises 1/T by the amount for `n` steps with energy standard deviation
+    /// `sigma` and acceptance rate `rho`.
+    fn cool(&mut self, n: F, sigma: F, rho: F) {
+        // Without fluctuations the update is undefined; keep the temperature.
+        if sigma > F::epsilon() {
+            let s: F = F::one() / self.temp;
+            let two = F::lit(2.0);
+            let rate: F = F::lit(4.0) * rho * (F::one() - rho).powi(2) / (two - rho).powi(2);
+            let ds: F = n * self.lambda / sigma / (s * sigma).powi(2) * rate;
+            self.temp = (F::one() / (s + ds)).max(self.t_final);
+        }
+    }
+
+    fn clear_window(&mut self) {
+        self.n = 0;
+        self.n_accepted = 0;
+        self.e_mean = F::zero();
+        self.m2 = F::zero();
+    }
+}
+
+impl<F: McFloat> CoolingSchedule<F> for Adaptive<F> {
+    #[inline]
+    fn temperature(&self, _step: u64) -> F {
+        return self.temp;
+    }
+
+    fn observe(&mut self, _step: u64, energy: F, accepted: bool) {
+        // Welford's running mean and variance
+        self.n += 1;
+        if accepted {
+            self.n_accepted += 1;
+        }
+        let diff = energy - self.e_mean;
+        self.e_mean += diff / F::from_u64(self.n).unwrap();
+        self.m2 += diff * (energy - self.e_mean);
+        if self.n < self.window {
+            return;
+        }
+
+        let n = F::from_u64(self.n).unwrap();
+        let sigma: F = (self.m2 / (n - F::one())).sqrt();
+        let rho: F = F::from_u64(self.n_accepted).unwrap() / n;
+        self.cool(n, sigma, rho);
+        self.clear_window();
+    }
+
+    // The population is a window of its own: one update per population step.
+    fn observe_population(&mut self, _step: u64, _e_mean: F, e_std: F, acceptance: F) {
+        self.cool(F::one(), e_std, acceptance);
+    }
+
+    fn reset(&mut self) {
+        self.temp = self.t_ini;
+        self.clear_window();
+    }
+
+    fn name(&self) -> String {
+        return "adaptive".to_string();
+    }
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_fitted_schedules_hit_end_points() {
+    let schedules: Vec<Box<dyn CoolingSchedule<f64>>> = vec![
+        Box::new(Geometric::new(2.0, 0.01, 1000)),
+        Box::new(Linear::new(2.0, 0.01, 1000)),
+        Box::new(Exponential::new(2.0, 0.01, 1000, 5)),
+        Box::new(LundyMees::new(2.0, 0.01, 1000)),
+        Box::new(Staircase::new(2.0, 0.01, 1000, 10)),
+    ];
+    for schedule in schedules {
+        assert!((schedule.temperature(0) - 2.0).abs() < 1e-12);
+        assert!(schedule.temperature(500) < 2.0);
+        assert!(schedule.temperature(500) > 0.01);
+        if schedule.name() != "staircase" {
+            assert!((schedule.temperature(1000) - 0.01).abs() < 1e-12);
+        }
+    }
+    let staircase: Staircase<f64> = Staircase::new(2.0, 0.01, 1000, 10);
+    assert!((staircase.temperature(999) - 0.01).abs() < 1e-12);
+}
+
+#[test]
+fn test_table_schedule_lookup() {
+    let table: Table<f32> = Table::new(vec![(10, 1.0), (100, 0.5), (200, 0.1)]);
+    assert_eq!(table.temperature(0), 1.0);
+    assert_eq!(table.temperature(99), 1.0);
+    assert_eq!(table.temperature(100), 0.5);
+    assert_eq!(table.temperature(5000), 0.1);
+}
+
+#[test]
+fn test_adaptive_schedule_cools_with_fluctuations() {
+    let mut schedule: Adaptive<f64> = Adaptive::new(1.0, 0.05, 10, 0.5);
+    for step in 0..9 {
+        schedule.observe(step, 1.0, false);
+    }
+    assert_eq!(schedule.temperature(9), 1.0);
+    // A window without any fluctuation leaves the temperature alone.
+    schedule.observe(9, 1.0, false);
+    assert_eq!(schedule.temperature(10), 1.0);
+
+    let mut temp: f64 = 1.0;
+    for step in 10..1000 {
+        schedule.observe(step, (step % 3) as f64, step % 2 == 0);
+        assert!(schedule.temperature(step + 1) <= temp);
+        temp = schedule.temperature(step + 1);
+    }
+    assert!(temp < 1.0);
+    assert!(temp >= 0.05);
+}
@@ -0,0 +1,258 @@
+use crate::ackley_mc::{run_mc_with_sink, AckleyMcParams};
+use crate::ensemble::run_parallel;
+use crate::float::McFloat;
+use crate::trajectory::SummarySink;
+
+// -------------------- parameter sweeps --------------------
+
+/// Parameters that can be given a list (`0.1,1,10`) or a range
+/// (`start:stop:step`, stop included) of values in the parameter file.
+pub const SWEEP_KEYS: [&str; 4] = ["x_delta", "ini_temp", "final_temp", "n_step"];
+
+/// One swept parameter and its values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepAxis {
+    pub key: String,
+    pub values: Vec<f64>,
+}
+
+/// Whether `s` is a list or range of values rather than a single one.
+pub fn is_sweep_value(s: &str) -> bool {
+    return s.contains(',') || s.contains(':');
+}
+
+/// Values of a list `a,b,c` or range `start:stop:step` (step > 0, stop
+/// included up to rounding). `None` if `s` is malformed.
+pub fn parse_sweep_values(s: &str) -> Option<Vec<f64>> {
+    if !s.contains(':') {
+        return s.split(',').map(|v| v.parse::<f64>().ok()).collect();
+    }
+    let parts: Vec<f64> = s
+        .split(':')
+        .map(|v| v.parse::<f64>().ok())
+        .collect::<Option<Vec<f64>>>()?;
+    let [start, stop, step] = parts[..] else {
+        return None;
+    };
+    if step <= 0.0 || stop < start {
+        return None;
+    }
+    // the tolerance keeps e.g. 0.1:1.0:0.1 from losing its last value to rounding
+    let n_values: usize = ((stop - start) / step + 1e-9).floor() as usize + 1;
+    // round to the decimals written, e.g. 0.30000000000000004 to 0.3
+    let decimals: Option<i32> = s
+        .split(':')
+        .map(|v| match v.split_once('.') {
+            _ if v.contains(['e', 'E']) => None,
+            Some((_, d)) => Some(d.len() as i32),
+            None => Some(0),
+        })
+        .try_fold(0, |max, d| d.map(|d| max.max(d)));
+    let round = |v: f64| match decimals {
+        Some(d) => (v * 10f64.powi(d)).round() / 10f64.powi(d),
+        None => v,
+    };
+    return Some(
+        (0..n_values)
+            .map(|k| round(start + k as f64 * step))
+            .collect(),
+    );
+}
+
+/// Sets the swept parameter `key` of `params` to `value`.
+pub fn set_swept_value<F: McFloat>(params: &mut AckleyMcParams<F>, key: &str, value: f64) {
+    match key {
+        "x_delta" => params.x_delta = F::lit(value),
+        "ini_temp" => params.ini_temp = F::lit(value),
+        "final_temp" => params.final_temp = F::lit(value),
+        "n_step" => params.n_step = value as u64,
+        _ => panic!(
+            "Unknown sweep parameter '{}', expected one of {:?}",
+            key, SWEEP_KEYS
+        ),
+    }
+}
+
+/// Values of the swept parameters at every point of the grid, the Cartesian
+/// product of the axes with the last axis varying fastest.
+pub fn sweep_grid(axes: &[SweepAxis]) -> Vec<Vec<f64>> {
+    let mut grid: Vec<Vec<f64>> = vec![vec![]];
+    for axis in axes {
+        grid = grid
+            .iter()
+            .flat_map(|point| {
+                axis.values.iter().map(move |&v| {
+                    let mut point: Vec<f64> = point.clone();
+                    point.push(v);
+                    return point;
+                })
+            })
+            .collect();
+    }
+    return grid;
+}
+
+/// Parameters of every point of the sweep over `params.sweep`, which are
+/// plain runs. A negative final_temp means the constant temperature
+/// ini_temp, as in the parameter file, so sweeping ini_temp alone sweeps
+/// constant temperatures.
+pub fn sweep_points<F: McFloat>(params: &AckleyMcParams<F>) -> Vec<AckleyMcParams<F>> {
+    return sweep_grid(&params.sweep)
+        .iter()
+        .map(|values| {
+            let mut point: AckleyMcParams<F> = params.clone();
+            point.sweep.clear();
+            for (axis, &value) in params.sweep.iter().zip(values) {
+                set_swept_value(&mut point, &axis.key, value);
+            }
+            if point.final_temp.is_sign_negative() {
+                point.final_temp = point.ini_temp;
+            }
+            return point;
+        })
+        .collect();
+}
+
+/// Outcome of one point of a sweep.
+#[derive(Clone, Debug)]
+pub struct SweepPoint<F: McFloat = f32> {
+    /// Values of the swept parameters, in the order of the axes.
+    pub values: Vec<f64>,
+    pub n_steps: u64,
+    pub n_accepted: u64,
+    /// Energy of the last recorded state.
+    pub e_final: F,
+    /// Mean energy of the recorded states.
+    pub e_mean: F,
+    pub e_best: F,
+    pub x_best: Vec<F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SweepResult<F: McFloat = f32> {
+    pub axes: Vec<SweepAxis>,
+    pub points: Vec<SweepPoint<F>>,
+}
+
+impl<F: McFloat> SweepResult<F> {
+    /// `value` over the grid of the first two axes as (x values, y values,
+    /// z[y][x]), averaged over the remaining axes. Needs two axes.
+    pub fn heat_map(
+        &self,
+        value: impl Fn(&SweepPoint<F>) -> f64,
+    ) -> (Vec<f64>, Vec<f64>, Vec<Vec<f64>>) {
+        let (xs, ys) = (&self.axes[0].values, &self.axes[1].values);
+        let mut sums: Vec<Vec<f64>> = vec![vec![0.0; xs.len()]; ys.len()];
+        let mut counts: Vec<Vec<u64>> = vec![vec![0; xs.len()]; ys.len()];
+        // the first two axes vary slowest, so the point index determines the cell
+        let n_rest: usize = self.points.len() / (xs.len() * ys.len());
+        for (k, point) in self.points.iter().enumerate() {
+            let (i, j) = (k / n_rest / ys.len(), k / n_rest % ys.len());
+            sums[j][i] += value(point);
+            counts[j][i] += 1;
+        }
+        let z: Vec<Vec<f64>> = sums
+            .iter()
+            .zip(&counts)
+            .map(|(row, n)| row.iter().zip(n).map(|(s, &n)| s / n as f64).collect())
+            .collect();
+        return (xs.clone(), ys.clone(), z);
+    }
+
+    /// Index of the point that found the lowest energy.
+    pub fn best_point(&self) -> usize {
+        let mut best: usize = 0;
+        for (k, point) in self.points.iter().enumerate() {
+            if point.e_best < self.points[best].e_best {
+                best = k;
+            }
+        }
+        return best;
+    }
+}
+
+/// Runs every point of the sweep over `params.sweep` in parallel, all with the
+/// same seed, keeping only summary statistics of the trajectories.
+pub fn run_sweep<F: McFloat>(params: AckleyMcParams<F>) -> SweepResult<F> {
+    let grid: Vec<Vec<f64>> = sweep_grid(&params.sweep);
+    let points: Vec<AckleyMcParams<F>> = sweep_points(&params);
+    let results: Vec<SweepPoint<F>> = run_parallel(&params, points.len(), |k, objective| {
+        let mut sink: SummarySink<F> = SummarySink::new();
+        let res = run_mc_with_sink(points[k].clone(), objective, &mut sink);
+        return SweepPoint {
+            values: grid[k].clone(),
+            n_steps: res.n_steps,
+            n_accepted: res.n_accepted,
+            e_final: sink.e_last,
+            e_mean: sink.e_mean,
+            e_best: res.e_best,
+            x_best: res.x_best,
+        };
+    });
+    return SweepResult {
+        axes: params.sweep,
+        points: results,
+    };
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_sweep_values_and_grid() {
+    assert_eq!(parse_sweep_values("0.1,1,10"), Some(vec![0.1, 1.0, 10.0]));
+    assert_eq!(parse_sweep_values("1000:3000:1000").unwrap().len(), 3);
+    let range: Vec<f64> = parse_sweep_values("0.1:1.0:0.1").unwrap();
+    assert_eq!(range.len(), 10);
+    assert_eq!(range[2], 0.3);
+    assert_eq!(parse_sweep_values("1:0:0.1"), None);
+    assert_eq!(parse_sweep_values("1:2"), None);
+    assert_eq!(parse_sweep_values("1,a"), None);
+
+    let axes: Vec<SweepAxis> = vec![
+        SweepAxis {
+            key: "ini_temp".to_string(),
+            values: vec![1.0, 2.0],
+        },
+        SweepAxis {
+            key: "x_delta".to_string(),
+            values: vec![0.1, 0.2, 0.3],
+        },
+    ];
+    let grid: Vec<Vec<f64>> = sweep_grid(&axes);
+    assert_eq!(grid.len(), 6);
+    assert_eq!(grid[1], vec![1.0, 0.2]);
+    assert_eq!(grid[3], vec![2.0, 0.1]);
+}
+
+#[test]
+fn test_run_sweep() {
+    use crate::ackley_mc::build_amcparams;
+
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.n_step = 200;
+    params.x_ini = vec![1.0, -1.0];
+    params.x_delta = 0.1;
+    params.sweep = vec![
+        SweepAxis {
+            key: "ini_temp".to_string(),
+            values: vec![0.1, 1.0],
+        },
+        SweepAxis {
+            key: "x_delta".to_string(),
+            values: vec![0.1, 0.5, 1.0],
+        },
+    ];
+
+    let points: Vec<AckleyMcParams<f64>> = sweep_points(&params);
+    assert_eq!((points[4].ini_temp, points[4].x_delta), (1.0, 0.5));
+    // no final_temp given: constant temperature
+    assert_eq!(points[4].final_temp, 1.0);
+
+    let res = run_sweep(params);
+    assert_eq!(res.points.len(), 6);
+    assert!(res.points.iter().all(|p| p.n_steps == 200));
+    let (xs, ys, z) = res.heat_map(|p| p.e_best);
+    assert_eq!((xs.len(), ys.len()), (2, 3));
+    assert_eq!(z[2][1], res.points[5].e_best);
+    assert_eq!(z[0][1], res.points[3].e_best);
+}
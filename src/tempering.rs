@@ -0,0 +1,207 @@
+use crate::ackley_mc::{build_amcresult, AckleyMcParams, AckleyMcResult, StepAdapt};
+use crate::chain::Chain;
+use crate::float::McFloat;
+use crate::objective::Objective;
+use crate::trajectory::{MemorySink, TrajectorySink};
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+// -------------------- parallel tempering --------------------
+
+/// Result of a replica exchange run. Replica k is the chain at temperature
+/// `temps[k]`; its trajectory follows whatever state currently sits at that
+/// temperature, so it jumps when a swap is accepted.
+#[derive(Clone, Debug)]
+pub struct TemperingResult<F: McFloat = f32> {
+    pub temps: Vec<F>,
+    pub replicas: Vec<AckleyMcResult<F>>,
+    /// Swap attempts and accepted swaps between replicas k and k + 1.
+    pub swap_attempts: Vec<u64>,
+    pub swap_accepts: Vec<u64>,
+}
+
+impl<F: McFloat> TemperingResult<F> {
+    /// Fraction of accepted swaps between replicas `pair` and `pair + 1`.
+    pub fn swap_rate(&self, pair: usize) -> f64 {
+        if self.swap_attempts[pair] == 0 {
+            return 0.0;
+        }
+        return self.swap_accepts[pair] as f64 / self.swap_attempts[pair] as f64;
+    }
+
+    /// Index of the replica that found the lowest energy.
+    pub fn best_replica(&self) -> usize {
+        let mut best: usize = 0;
+        for (k, replica) in self.replicas.iter().enumerate() {
+            if replica.e_best < self.replicas[best].e_best {
+                best = k;
+            }
+        }
+        return best;
+    }
+}
+
+/// Temperatures of the replicas: `pt_temps` if given, otherwise a geometric
+/// ladder of `pt_replicas` temperatures from `ini_temp` to `final_temp`.
+pub fn temperature_ladder<F: McFloat>(params: &AckleyMcParams<F>) -> Vec<F> {
+    if !params.pt_temps.is_empty() {
+        return params.pt_temps.clone();
+    }
+    let n: usize = params.pt_replicas;
+    if n == 1 {
+        return vec![params.ini_temp];
+    }
+    let ratio: F = params.final_temp / params.ini_temp;
+    let temps: Vec<F> = (0..n)
+        .map(|k| {
+            let frac: F = F::from_usize(k).unwrap() / F::from_usize(n - 1).unwrap();
+            return params.ini_temp * ratio.powf(frac);
+        })
+        .collect();
+    return temps;
+}
+
+/// Replica exchange Monte Carlo: one Metropolis chain per temperature of the
+/// ladder, all started at x_ini. Every `pt_swap_interval` steps neighbouring
+/// replicas try to exchange their states, alternating between the even and
+/// the odd pairs, accepted with probability
+/// min(1, exp((1 / T_k - 1 / T_k+1) (E_k - E_k+1))).
+pub fn run_parallel_tempering<F: McFloat, O: Objective<F> + ?Sized>(
+    params: AckleyMcParams<F>,
+    objective: &O,
+) -> TemperingResult<F> {
+    let temps: Vec<F> = temperature_ladder(&params);
+    let n_replicas: usize = temps.len();
+    // the swaps have their own random numbers, the chains use seed + k + 1
+    let mut rng = ChaCha8Rng::seed_from_u64(params.seed);
+    let mut chains: Vec<Chain<F, O>> = (0..n_replicas)
+        .map(|k| {
+            Chain::new(
+                &params,
+                objective,
+                &params.x_ini,
+                params.seed.wrapping_add(k as u64 + 1),
+            )
+        })
+        .collect();
+    let mut sinks: Vec<MemorySink<F>> = (0..n_replicas)
+        .map(|_| MemorySink::with_capacity(params.n_records() as usize))
+        .collect();
+    let adapt: bool = params.step_adapt != StepAdapt::None;
+
+    let mut swap_attempts: Vec<u64> = vec![0; n_replicas.saturating_sub(1)];
+    let mut swap_accepts: Vec<u64> = vec![0; n_replicas.saturating_sub(1)];
+    let mut n_swap_rounds: u64 = 0;
+
+    // whether the state of replica k changed since it was last recorded
+    let mut moved: Vec<bool> = vec![true; n_replicas];
+    if params.is_recorded(0) {
+        for k in 0..n_replicas {
+            sinks[k].record(0, &chains[k].x, chains[k].energy, true);
+            sinks[k].record_temperature(0, temps[k]);
+            moved[k] = false;
+        }
+    }
+
+    for step in 0..params.n_step {
+        for (k, chain) in chains.iter_mut().enumerate() {
+            let e_trial: F = chain.propose(temps[k]);
+            let accept: bool = chain.metropolis(e_trial, temps[k]);
+            if accept {
+                chain.accept(e_trial);
+                sinks[k].record_accepted(step);
+                moved[k] = true;
+            } else {
+                chain.reject();
+            }
+            if adapt && step < params.burn_in {
+                chain.adapt_step_size(accept, params.target_accept);
+            }
+        }
+
+        if (step + 1).is_multiple_of(params.pt_swap_interval) {
+            let first: usize = (n_swap_rounds % 2) as usize;
+            for k in (first..n_replicas.saturating_sub(1)).step_by(2) {
+                swap_attempts[k] += 1;
+                let (lower, upper) = chains.split_at_mut(k + 1);
+                let (this, next) = (&mut lower[k], &mut upper[0]);
+                let d_beta: F = temps[k].recip() - temps[k + 1].recip();
+                let log_alpha: F = d_beta * (this.energy - next.energy);
+                if log_alpha >= F::zero() || rng.gen_range(F::zero()..F::one()) < log_alpha.exp() {
+                    this.swap_states(next);
+                    swap_accepts[k] += 1;
+                    moved[k] = true;
+                    moved[k + 1] = true;
+                }
+            }
+            n_swap_rounds += 1;
+        }
+
+        if params.is_recorded(step + 1) {
+            for k in 0..n_replicas {
+                sinks[k].record(step + 1, &chains[k].x, chains[k].energy, moved[k]);
+                sinks[k].record_temperature(step + 1, temps[k]);
+                moved[k] = false;
+            }
+        }
+    }
+
+    let replicas: Vec<AckleyMcResult<F>> = chains
+        .into_iter()
+        .zip(sinks)
+        .map(|(chain, sink)| {
+            let mut res: AckleyMcResult<F> = build_amcresult();
+            res.objective = objective.name();
+            res.n_steps = params.n_step;
+            res.n_accepted = chain.n_accepted;
+            res.steps = sink.steps;
+            res.accepted_steps = sink.accepted_steps;
+            res.x_vals = sink.x_vals;
+            res.e_vals = sink.e_vals;
+            res.t_vals = sink.t_vals;
+            if adapt {
+                res.x_deltas.push((params.burn_in, chain.x_deltas.clone()));
+            }
+            res.e_best = chain.e_best;
+            res.x_best = chain.x_best;
+            return res;
+        })
+        .collect();
+
+    return TemperingResult {
+        temps,
+        replicas,
+        swap_attempts,
+        swap_accepts,
+    };
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_parallel_tempering() {
+    use crate::ackley_mc::{build_amcparams, Ackley};
+
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.mode = "parallel_tempering".to_string();
+    params.ini_temp = 1.0;
+    params.final_temp = 0.01;
+    params.pt_replicas = 5;
+    params.n_step = 2000;
+    params.x_ini = vec![3.0, -2.0];
+    params.x_delta = 0.5;
+
+    let temps = temperature_ladder(&params);
+    assert_eq!(temps.len(), 5);
+    assert!((temps[2] - 0.1).abs() < 1e-12);
+
+    let res = run_parallel_tempering(params.clone(), &Ackley::default());
+    assert_eq!(res.replicas.len(), 5);
+    assert_eq!(res.swap_attempts.iter().sum::<u64>(), 2 * 200);
+    assert!(res.swap_accepts.iter().all(|&n| n > 0));
+    for replica in &res.replicas {
+        assert_eq!(replica.e_vals.len() as u64, params.n_step + 1);
+        assert!(replica.t_vals.iter().all(|&t| t == replica.t_vals[0]));
+    }
+    assert!(res.replicas[res.best_replica()].e_best < 0.1);
+}
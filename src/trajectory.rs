@@ -0,0 +1,301 @@
+use crate::float::{to_f64, McFloat};
+use std::io::Write;
+
+// -------------------- trajectory sinks --------------------
+
+/// Receives the states recorded during a Monte Carlo run. Step 0 is the
+/// initial state, step k is the state after the k-th trial move. `accepted`
+/// tells whether the state changed since the previous record (always true for
+/// the first one), i.e. whether a move was accepted in between.
+pub trait TrajectorySink<F: McFloat> {
+    fn record(&mut self, step: u64, x: &[F], e: F, accepted: bool);
+
+    /// Temperature the state recorded at `step` was sampled at, called right
+    /// after `record`. Ignored unless the sink keeps temperatures.
+    fn record_temperature(&mut self, _step: u64, _temperature: F) {}
+
+    /// Called for every accepted trial move (0-based index), recorded or
+    /// not. Ignored unless the sink keeps the accepted moves.
+    fn record_accepted(&mut self, _step: u64) {}
+
+    /// Called once after the last step, e.g. to flush buffered output.
+    fn finish(&mut self) {}
+}
+
+/// Keeps the whole trajectory in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemorySink<F: McFloat> {
+    pub steps: Vec<u64>,
+    /// Indices (0-based) of all accepted trial moves, also during the
+    /// burn-in and between thinned records.
+    pub accepted_steps: Vec<u64>,
+    pub x_vals: Vec<Vec<F>>,
+    pub e_vals: Vec<F>,
+    pub t_vals: Vec<F>,
+}
+
+impl<F: McFloat> MemorySink<F> {
+    pub fn new() -> Self {
+        return MemorySink {
+            steps: vec![],
+            accepted_steps: vec![],
+            x_vals: vec![],
+            e_vals: vec![],
+            t_vals: vec![],
+        };
+    }
+
+    pub fn with_capacity(n_records: usize) -> Self {
+        return MemorySink {
+            steps: Vec::with_capacity(n_records),
+            accepted_steps: vec![],
+            x_vals: Vec::with_capacity(n_records),
+            e_vals: Vec::with_capacity(n_records),
+            t_vals: Vec::with_capacity(n_records),
+        };
+    }
+}
+
+impl<F: McFloat> TrajectorySink<F> for MemorySink<F> {
+    fn record(&mut self, step: u64, x: &[F], e: F, _accepted: bool) {
+        self.steps.push(step);
+        self.x_vals.push(x.to_vec());
+        self.e_vals.push(e);
+    }
+
+    fn record_temperature(&mut self, _step: u64, temperature: F) {
+        self.t_vals.push(temperature);
+    }
+
+    fn record_accepted(&mut self, step: u64) {
+        self.accepted_steps.push(step);
+    }
+}
+
+/// Streams csv lines (step,x_1,...,x_n,energy) in the format the in-memory
+/// writer always had: whenever a recorded state changed, a line with the
+/// previous record, i.e. without thinning the state an accepted move started
+/// from, labelled with the index of the move. A final line holds the last
+/// step with the state of the last line written (the first record if none).
+pub struct CsvSink<F: McFloat, W: Write> {
+    out: W,
+    started: bool,
+    last_step: u64,
+    // previous record
+    prev_step: u64,
+    prev_x: Vec<F>,
+    prev_e: F,
+    // state of the last line written
+    line_x: Vec<F>,
+    line_e: F,
+}
+
+impl<F: McFloat, W: Write> CsvSink<F, W> {
+    pub fn new(out: W) -> Self {
+        return CsvSink {
+            out,
+            started: false,
+            last_step: 0,
+            prev_step: 0,
+            prev_x: vec![],
+            prev_e: F::zero(),
+            line_x: vec![],
+            line_e: F::zero(),
+        };
+    }
+}
+
+impl<F: McFloat, W: Write> TrajectorySink<F> for CsvSink<F, W> {
+    fn record(&mut self, step: u64, x: &[F], e: F, accepted: bool) {
+        self.last_step = step;
+        if !self.started {
+            self.started = true;
+            self.line_x = x.to_vec();
+            self.line_e = e;
+        } else if accepted {
+            let line = to_csv_line(self.prev_step as usize, &self.prev_x, self.prev_e);
+            writeln!(self.out, "{}", line).expect("Could not write trajectory csv line");
+            self.line_x.copy_from_slice(&self.prev_x);
+            self.line_e = self.prev_e;
+        }
+        self.prev_step = step;
+        self.prev_x.clear();
+        self.prev_x.extend_from_slice(x);
+        self.prev_e = e;
+    }
+
+    fn finish(&mut self) {
+        if self.started {
+            let line = to_csv_line(self.last_step as usize, &self.line_x, self.line_e);
+            writeln!(self.out, "{}", line).expect("Could not write trajectory csv line");
+        }
+        self.out.flush().expect("Could not flush trajectory csv");
+    }
+}
+
+/// Streams every step as little-endian binary: a u64 header with the
+/// dimension, then per step a u64 step index, the coordinates and the energy
+/// as f64.
+pub struct BinarySink<W: Write> {
+    out: W,
+    header_written: bool,
+}
+
+impl<W: Write> BinarySink<W> {
+    pub fn new(out: W) -> Self {
+        return BinarySink {
+            out,
+            header_written: false,
+        };
+    }
+}
+
+impl<F: McFloat, W: Write> TrajectorySink<F> for BinarySink<W> {
+    fn record(&mut self, step: u64, x: &[F], e: F, _accepted: bool) {
+        if !self.header_written {
+            self.out
+                .write_all(&(x.len() as u64).to_le_bytes())
+                .expect("Could not write binary trajectory");
+            self.header_written = true;
+        }
+        self.out
+            .write_all(&step.to_le_bytes())
+            .expect("Could not write binary trajectory");
+        for &xi in x {
+            self.out
+                .write_all(&to_f64(xi).to_le_bytes())
+                .expect("Could not write binary trajectory");
+        }
+        self.out
+            .write_all(&to_f64(e).to_le_bytes())
+            .expect("Could not write binary trajectory");
+    }
+
+    fn finish(&mut self) {
+        self.out.flush().expect("Could not flush binary trajectory");
+    }
+}
+
+/// Keeps only summary statistics: energy mean/variance (Welford), extrema,
+/// last energy, mean coordinates and acceptance count.
+#[derive(Clone, Debug, Default)]
+pub struct SummarySink<F: McFloat> {
+    pub n_records: u64,
+    pub n_accepted: u64,
+    pub e_mean: F,
+    m2: F,
+    pub e_min: F,
+    pub e_max: F,
+    /// Energy of the last recorded state.
+    pub e_last: F,
+    pub x_mean: Vec<F>,
+}
+
+impl<F: McFloat> SummarySink<F> {
+    pub fn new() -> Self {
+        return SummarySink {
+            n_records: 0,
+            n_accepted: 0,
+            e_mean: F::zero(),
+            m2: F::zero(),
+            e_min: F::infinity(),
+            e_max: F::neg_infinity(),
+            e_last: F::zero(),
+            x_mean: vec![],
+        };
+    }
+
+    /// Sample variance of the recorded energies.
+    pub fn e_variance(&self) -> F {
+        if self.n_records < 2 {
+            return F::zero();
+        }
+        return self.m2 / F::from_u64(self.n_records - 1).unwrap();
+    }
+}
+
+impl<F: McFloat> TrajectorySink<F> for SummarySink<F> {
+    fn record(&mut self, _step: u64, x: &[F], e: F, accepted: bool) {
+        if self.x_mean.is_empty() {
+            self.x_mean = vec![F::zero(); x.len()];
+        }
+        self.n_records += 1;
+        if accepted {
+            self.n_accepted += 1;
+        }
+
+        let n = F::from_u64(self.n_records).unwrap();
+        let diff = e - self.e_mean;
+        self.e_mean += diff / n;
+        self.m2 += diff * (e - self.e_mean);
+        self.e_min = self.e_min.min(e);
+        self.e_max = self.e_max.max(e);
+        self.e_last = e;
+        for (mean, &xi) in self.x_mean.iter_mut().zip(x) {
+            *mean += (xi - *mean) / n;
+        }
+    }
+}
+
+pub fn to_csv_line<F: McFloat>(step: usize, x: &[F], e: F) -> String {
+    let xstr: String = x.iter().map(|&xi| xi.to_string() + ",").collect();
+    return format!("{},{}{}", step, xstr.as_str(), e);
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_csv_sink_accepted_and_final_lines() {
+    let mut out: Vec<u8> = vec![];
+    {
+        let mut sink: CsvSink<f32, _> = CsvSink::new(&mut out);
+        sink.record(0, &[1.0, 2.0], 3.0, true);
+        sink.record(1, &[1.5, 2.0], 2.5, true);
+        sink.record(2, &[1.5, 2.0], 2.5, false);
+        sink.finish();
+    }
+    // the state the accepted move 0 started from, then the last step
+    assert_eq!(String::from_utf8(out).unwrap(), "0,1,2,3\n2,1,2,3\n");
+}
+
+#[test]
+fn test_csv_sink_matches_baseline_output() {
+    use crate::ackley_mc::{build_amcparams, run_mc_with_sink, Ackley, AckleyMcParams};
+
+    let mut params: AckleyMcParams<f32> = build_amcparams();
+    params.ini_temp = 1.0;
+    params.final_temp = 0.1;
+    params.n_step = 12;
+    params.x_ini = vec![1.5, -0.5];
+    params.x_delta = 0.5;
+    params.seed = 7;
+    let mut out: Vec<u8> = vec![];
+    run_mc_with_sink(params, &Ackley::default(), &mut CsvSink::new(&mut out));
+
+    // written by write_res_to_file before trajectory sinks existed
+    let baseline: &str = "0,1.5,-0.5,6.357813\n\
+                          1,1.157796,-0.5,5.1878357\n\
+                          2,0.8257853,-0.5,4.5077877\n\
+                          4,0.8257853,-0.96389425,3.9575157\n\
+                          5,0.4088447,-0.96389425,4.4016037\n\
+                          6,0.6455171,-0.96389425,4.544821\n\
+                          7,0.6455171,-0.9426445,4.5254707\n\
+                          8,0.67205477,-0.9426445,4.4767323\n\
+                          11,0.8469827,-0.9426445,3.8742123\n\
+                          12,0.8469827,-0.9426445,3.8742123\n";
+    assert_eq!(String::from_utf8(out).unwrap(), baseline);
+}
+
+#[test]
+fn test_summary_sink_statistics() {
+    let mut sink: SummarySink<f64> = SummarySink::new();
+    for (step, e) in [1.0, 2.0, 3.0, 4.0].iter().enumerate() {
+        sink.record(step as u64, &[*e, -*e], *e, step % 2 == 0);
+    }
+    assert_eq!(sink.n_records, 4);
+    assert_eq!(sink.n_accepted, 2);
+    assert!((sink.e_mean - 2.5).abs() < 1e-12);
+    assert!((sink.e_variance() - 5.0 / 3.0).abs() < 1e-12);
+    assert_eq!((sink.e_min, sink.e_max, sink.e_last), (1.0, 4.0, 4.0));
+    assert!((sink.x_mean[1] + 2.5).abs() < 1e-12);
+}
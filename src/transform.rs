@@ -0,0 +1,247 @@
+use crate::float::McFloat;
+use crate::objective::{DeltaObjective, Objective};
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use rand_distr::StandardNormal;
+use std::cell::RefCell;
+
+// -------------------- shift --------------------
+
+/// Evaluates the inner objective at x - shift, moving its optimum away from
+/// the origin (CEC convention).
+#[derive(Clone, Debug)]
+pub struct Shifted<F: McFloat, O: Objective<F>> {
+    pub inner: O,
+    pub shift: Vec<F>,
+    // x - shift, reused so that evaluations do not allocate
+    z: RefCell<Vec<F>>,
+}
+
+impl<F: McFloat, O: Objective<F>> Shifted<F, O> {
+    pub fn new(inner: O, shift: Vec<F>) -> Self {
+        let z: RefCell<Vec<F>> = RefCell::new(vec![F::zero(); shift.len()]);
+        return Shifted { inner, shift, z };
+    }
+
+    fn shift_into(&self, x: &[F], z: &mut [F]) {
+        for ((zi, &xi), &oi) in z.iter_mut().zip(x).zip(&self.shift) {
+            *zi = xi - oi;
+        }
+    }
+}
+
+impl<F: McFloat, O: Objective<F>> Objective<F> for Shifted<F, O> {
+    fn evaluate(&self, x: &[F]) -> F {
+        let mut z = self.z.borrow_mut();
+        self.shift_into(x, &mut z);
+        return self.inner.evaluate(&z);
+    }
+
+    fn dim(&self) -> Option<usize> {
+        return Some(self.shift.len());
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return self.inner.domain();
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        let (x_min, e_min) = self.inner.global_minimum(dim)?;
+        let x_min: Vec<F> = x_min
+            .iter()
+            .zip(&self.shift)
+            .map(|(&xi, &oi)| xi + oi)
+            .collect();
+        return Some((x_min, e_min));
+    }
+
+    fn name(&self) -> String {
+        return format!("Shifted {}", self.inner.name());
+    }
+
+    fn delta(&self) -> Option<&dyn DeltaObjective<F>> {
+        self.inner.delta()?;
+        return Some(self);
+    }
+
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        let mut z = self.z.borrow_mut();
+        self.shift_into(x, &mut z);
+        return self.inner.gradient(&z, grad);
+    }
+}
+
+/// A shift keeps coordinates independent, so the inner running sums still apply.
+impl<F: McFloat, O: Objective<F>> DeltaObjective<F> for Shifted<F, O> {
+    fn n_sums(&self) -> usize {
+        return self.inner.delta().unwrap().n_sums();
+    }
+
+    fn accumulate(&self, sums: &mut [F], i: usize, xi: F, weight: F) {
+        let inner = self.inner.delta().unwrap();
+        inner.accumulate(sums, i, xi - self.shift[i], weight);
+    }
+
+    fn energy_from_sums(&self, sums: &[F], dim: usize) -> F {
+        return self.inner.delta().unwrap().energy_from_sums(sums, dim);
+    }
+}
+
+// -------------------- rotation --------------------
+
+/// Evaluates the inner objective at M x for an orthogonal matrix M (stored
+/// row-wise), which makes separable functions non-separable.
+#[derive(Clone, Debug)]
+pub struct Rotated<F: McFloat, O: Objective<F>> {
+    pub inner: O,
+    pub matrix: Vec<Vec<F>>,
+    // M x and the inner gradient at it, reused so that evaluations do not allocate
+    z: RefCell<Vec<F>>,
+    grad_z: RefCell<Vec<F>>,
+}
+
+impl<F: McFloat, O: Objective<F>> Rotated<F, O> {
+    pub fn new(inner: O, matrix: Vec<Vec<F>>) -> Self {
+        assert!(
+            is_orthogonal(&matrix, F::lit(1e-4)),
+            "Rotation matrix must be square and orthogonal"
+        );
+        let z: RefCell<Vec<F>> = RefCell::new(vec![F::zero(); matrix.len()]);
+        let grad_z: RefCell<Vec<F>> = RefCell::new(vec![F::zero(); matrix.len()]);
+        return Rotated {
+            inner,
+            matrix,
+            z,
+            grad_z,
+        };
+    }
+
+    fn rotate_into(&self, x: &[F], z: &mut [F]) {
+        for (zi, row) in z.iter_mut().zip(&self.matrix) {
+            *zi = row.iter().zip(x).map(|(&mij, &xj)| mij * xj).sum();
+        }
+    }
+}
+
+impl<F: McFloat, O: Objective<F>> Objective<F> for Rotated<F, O> {
+    fn evaluate(&self, x: &[F]) -> F {
+        let mut z = self.z.borrow_mut();
+        self.rotate_into(x, &mut z);
+        return self.inner.evaluate(&z);
+    }
+
+    fn dim(&self) -> Option<usize> {
+        return Some(self.matrix.len());
+    }
+
+    fn domain(&self) -> Option<(F, F)> {
+        return self.inner.domain();
+    }
+
+    fn global_minimum(&self, dim: usize) -> Option<(Vec<F>, F)> {
+        // M is orthogonal, so M x = x_min is solved by x = M^T x_min.
+        let (x_min, e_min) = self.inner.global_minimum(dim)?;
+        let x_min: Vec<F> = (0..self.matrix.len())
+            .map(|j| {
+                self.matrix
+                    .iter()
+                    .zip(&x_min)
+                    .map(|(row, &zi)| row[j] * zi)
+                    .sum()
+            })
+            .collect();
+        return Some((x_min, e_min));
+    }
+
+    fn name(&self) -> String {
+        return format!("Rotated {}", self.inner.name());
+    }
+
+    /// Chain rule: the gradient of f(M x) is M^T (grad f)(M x).
+    fn gradient(&self, x: &[F], grad: &mut [F]) -> bool {
+        let mut z = self.z.borrow_mut();
+        let mut grad_z = self.grad_z.borrow_mut();
+        self.rotate_into(x, &mut z);
+        if !self.inner.gradient(&z, &mut grad_z) {
+            return false;
+        }
+        for (j, g) in grad.iter_mut().enumerate() {
+            *g = self
+                .matrix
+                .iter()
+                .zip(grad_z.iter())
+                .map(|(row, &gz)| row[j] * gz)
+                .sum();
+        }
+        return true;
+    }
+}
+
+/// Random orthogonal `dim` x `dim` matrix, reproducible from `seed`.
+/// Gram-Schmidt orthonormalisation of a matrix with standard normal entries.
+pub fn random_rotation<F: McFloat>(dim: usize, seed: u64) -> Vec<Vec<F>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut rows: Vec<Vec<f64>> = Vec::with_capacity(dim);
+
+    while rows.len() < dim {
+        let mut v: Vec<f64> = (0..dim).map(|_| rng.sample(StandardNormal)).collect();
+        for row in &rows {
+            let proj: f64 = row.iter().zip(&v).map(|(ri, vi)| ri * vi).sum();
+            for (vi, ri) in v.iter_mut().zip(row) {
+                *vi -= proj * ri;
+            }
+        }
+        let norm: f64 = v.iter().map(|vi| vi * vi).sum::<f64>().sqrt();
+        // Practically never happens, but a degenerate draw is simply redrawn.
+        if norm < 1e-8 {
+            continue;
+        }
+        rows.push(v.iter().map(|vi| vi / norm).collect());
+    }
+
+    return rows
+        .iter()
+        .map(|row| row.iter().map(|&mij| F::lit(mij)).collect())
+        .collect();
+}
+
+pub fn is_orthogonal<F: McFloat>(matrix: &[Vec<F>], tolerance: F) -> bool {
+    let n = matrix.len();
+    if matrix.iter().any(|row| row.len() != n) {
+        return false;
+    }
+    for i in 0..n {
+        for j in 0..n {
+            let dot: F = matrix[i].iter().zip(&matrix[j]).map(|(&a, &b)| a * b).sum();
+            let expected: F = if i == j { F::one() } else { F::zero() };
+            if (dot - expected).abs() > tolerance {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_random_rotation_is_orthogonal() {
+    let m: Vec<Vec<f64>> = random_rotation(7, 42);
+    assert!(is_orthogonal(&m, 1e-12));
+    assert_eq!(m, random_rotation(7, 42));
+}
+
+#[test]
+fn test_shifted_rotated_global_minimum() {
+    use crate::landscapes::Rastrigin;
+
+    let shift: Vec<f32> = vec![1.5, -2.0, 0.25];
+    let objective = Shifted::new(Rotated::new(Rastrigin, random_rotation(3, 7)), shift);
+    let (x_min, e_min) = objective.global_minimum(3).unwrap();
+    assert!((objective.evaluate(&x_min) - e_min).abs() <= 0.001);
+    assert!(objective.evaluate(&[0.0, 0.0, 0.0]) > 1.0);
+
+    let mut grad: Vec<f32> = vec![1.0; 3];
+    assert!(objective.gradient(&x_min, &mut grad));
+    assert!(grad.iter().all(|g| g.abs() < 0.01));
+}
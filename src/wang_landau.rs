@@ -0,0 +1,211 @@
+use crate::ackley_mc::AckleyMcParams;
+use crate::chain::Chain;
+use crate::float::{to_f64, McFloat};
+use crate::objective::Objective;
+use rand::prelude::*;
+
+// -------------------- wang-landau --------------------
+
+/// How the modification factor ln f is reduced: halved whenever the
+/// histogram is flat (Wang-Landau), or halved until it drops below 1 / t and
+/// set to 1 / t from then on, t being the number of steps per bin
+/// (Belardinelli-Pereyra), which avoids the saturation of the error.
+pub const WL_SCHEDULES: [&str; 2] = ["halve", "one_over_t"];
+
+#[derive(Clone, Debug)]
+pub struct WangLandauResult<F: McFloat = f32> {
+    pub objective: String,
+    /// Lower edge and width of the energy bins.
+    pub e_lower: f64,
+    pub bin_width: f64,
+    /// ln g(E) per bin, shifted so that the smallest visited bin is 0.
+    pub ln_g: Vec<f64>,
+    /// Whether the walk ever reached the bin; ln g is meaningless otherwise.
+    pub visited: Vec<bool>,
+    /// Modification factor the run stopped at.
+    pub ln_f: f64,
+    /// Number of flat histograms, i.e. reductions of ln f by halving.
+    pub n_flat: u64,
+    pub n_steps: u64,
+    pub n_accepted: u64,
+    pub x_best: Vec<F>,
+    pub e_best: F,
+}
+
+impl<F: McFloat> WangLandauResult<F> {
+    /// Energy at the centre of bin `k`.
+    pub fn bin_energy(&self, k: usize) -> f64 {
+        return self.e_lower + (k as f64 + 0.5) * self.bin_width;
+    }
+
+    /// Canonical mean energy and heat capacity at `temperature`, recomputed
+    /// from the density of states over the visited bins.
+    pub fn canonical_averages(&self, temperature: f64) -> (f64, f64) {
+        let log_weights: Vec<(f64, f64)> = (0..self.ln_g.len())
+            .filter(|&k| self.visited[k])
+            .map(|k| {
+                let e: f64 = self.bin_energy(k);
+                return (e, self.ln_g[k] - e / temperature);
+            })
+            .collect();
+        let w_max: f64 = log_weights
+            .iter()
+            .map(|&(_, w)| w)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let (mut z, mut e_sum, mut e2_sum) = (0.0, 0.0, 0.0);
+        for &(e, w) in &log_weights {
+            let weight: f64 = (w - w_max).exp();
+            z += weight;
+            e_sum += weight * e;
+            e2_sum += weight * e * e;
+        }
+        let e_mean: f64 = e_sum / z;
+        let heat_capacity: f64 = (e2_sum / z - e_mean * e_mean) / (temperature * temperature);
+        return (e_mean, heat_capacity);
+    }
+}
+
+/// Wang-Landau random walk in energy over `wl_bins` bins between `wl_e_min`
+/// and `wl_e_max`: a move from E to E' is accepted with probability
+/// min(1, g(E) / g(E')), after which ln g of the current bin grows by ln f.
+/// Every `wl_check_interval` steps the histogram is checked for flatness
+/// (every visited bin at least `wl_flatness` times the mean), which resets
+/// it and reduces ln f (see `WL_SCHEDULES`). Stops after n_step steps or once
+/// ln f falls below `wl_ln_f_final`. Moves leaving the energy range are
+/// rejected; while x_ini lies outside, moves towards the range are accepted.
+pub fn run_wang_landau<F: McFloat, O: Objective<F> + ?Sized>(
+    params: AckleyMcParams<F>,
+    objective: &O,
+) -> WangLandauResult<F> {
+    let mut chain: Chain<F, O> = Chain::new(&params, objective, &params.x_ini, params.seed);
+    let n_bins: usize = params.wl_bins;
+    let e_lower: f64 = to_f64(params.wl_e_min);
+    let e_upper: f64 = to_f64(params.wl_e_max);
+    let bin_width: f64 = (e_upper - e_lower) / n_bins as f64;
+    let bin = |e: F| -> Option<usize> {
+        let e: f64 = to_f64(e);
+        if !(e_lower..e_upper).contains(&e) {
+            return None;
+        }
+        return Some((((e - e_lower) / bin_width) as usize).min(n_bins - 1));
+    };
+    let distance = |e: F| -> f64 { (e_lower - to_f64(e)).max(to_f64(e) - e_upper) };
+
+    let mut ln_g: Vec<f64> = vec![0.0; n_bins];
+    let mut histogram: Vec<u64> = vec![0; n_bins];
+    let mut visited: Vec<bool> = vec![false; n_bins];
+    let mut ln_f: f64 = 1.0;
+    let mut n_flat: u64 = 0;
+    let one_over_t: bool = params.wl_schedule == "one_over_t";
+    // reached ln f < 1 / t, ln f follows 1 / t from now on
+    let mut following_t: bool = false;
+
+    for step in 0..params.n_step {
+        // Only the scale-free proposals make sense here, there is no temperature.
+        let e_trial: F = chain.propose(F::one());
+        let accept: bool = match (bin(chain.energy), bin(e_trial)) {
+            (Some(k_old), Some(k_new)) => {
+                let log_alpha: f64 = ln_g[k_old] - ln_g[k_new];
+                log_alpha >= 0.0 || chain.rng.gen_range(0.0..1.0) < log_alpha.exp()
+            }
+            (Some(_), None) => false,
+            (None, _) => distance(e_trial) < distance(chain.energy),
+        };
+        if accept {
+            chain.accept(e_trial);
+        } else {
+            chain.reject();
+        }
+
+        let Some(k) = bin(chain.energy) else {
+            continue;
+        };
+        ln_g[k] += ln_f;
+        histogram[k] += 1;
+        visited[k] = true;
+
+        if following_t {
+            let n_visited: usize = visited.iter().filter(|&&v| v).count();
+            ln_f = n_visited as f64 / (step + 1) as f64;
+        } else if (step + 1).is_multiple_of(params.wl_check_interval)
+            && is_flat(&histogram, &visited, to_f64(params.wl_flatness))
+        {
+            histogram.iter_mut().for_each(|h| *h = 0);
+            ln_f *= 0.5;
+            n_flat += 1;
+            let n_visited: usize = visited.iter().filter(|&&v| v).count();
+            if one_over_t && ln_f < n_visited as f64 / (step + 1) as f64 {
+                following_t = true;
+            }
+        }
+        if ln_f < to_f64(params.wl_ln_f_final) {
+            break;
+        }
+    }
+
+    let ln_g_min: f64 = (0..n_bins)
+        .filter(|&k| visited[k])
+        .map(|k| ln_g[k])
+        .fold(f64::INFINITY, f64::min);
+    for (k, g) in ln_g.iter_mut().enumerate() {
+        *g = if visited[k] { *g - ln_g_min } else { 0.0 };
+    }
+
+    return WangLandauResult {
+        objective: objective.name(),
+        e_lower,
+        bin_width,
+        ln_g,
+        visited,
+        ln_f,
+        n_flat,
+        n_steps: chain.n_steps,
+        n_accepted: chain.n_accepted,
+        x_best: chain.x_best,
+        e_best: chain.e_best,
+    };
+}
+
+/// Whether every visited bin has at least `flatness` times the mean count.
+fn is_flat(histogram: &[u64], visited: &[bool], flatness: f64) -> bool {
+    let counts: Vec<u64> = histogram
+        .iter()
+        .zip(visited)
+        .filter(|(_, &v)| v)
+        .map(|(&h, _)| h)
+        .collect();
+    if counts.is_empty() {
+        return false;
+    }
+    let mean: f64 = counts.iter().sum::<u64>() as f64 / counts.len() as f64;
+    return counts.iter().all(|&h| h as f64 >= flatness * mean);
+}
+
+// -------------------- unit tests --------------------
+
+#[test]
+fn test_wang_landau_sphere_density_of_states() {
+    use crate::ackley_mc::build_amcparams;
+    use crate::landscapes::Sphere;
+
+    // In 2D the sphere has g(E) ~ const, i.e. flat ln g.
+    let mut params: AckleyMcParams<f64> = build_amcparams();
+    params.mode = "wang_landau".to_string();
+    params.n_step = 2_000_000;
+    params.x_ini = vec![0.5, 0.5];
+    params.x_delta = 0.5;
+    params.wl_e_min = 0.0;
+    params.wl_e_max = 4.0;
+    params.wl_bins = 20;
+    params.wl_ln_f_final = 1e-4;
+
+    let res = run_wang_landau(params, &Sphere);
+    assert!(res.visited.iter().all(|&v| v));
+    assert!(res.n_flat > 0);
+    let spread: f64 = res.ln_g.iter().copied().fold(0.0, f64::max);
+    assert!(spread < 0.3, "ln g should be flat, spread {}", spread);
+
+    // canonical <E> of the 2D sphere (truncated at E = 4) is ~ T for small T
+    let (e_mean, _) = res.canonical_averages(0.5);
+    assert!((e_mean - 0.5).abs() < 0.1);
+}